@@ -36,6 +36,18 @@ impl ArrayBuffer {
             );
         }
     }
+
+    /// For data replaced every frame, such as instance transforms.
+    pub fn stream_draw_data<T>(&self, data: &[T]) {
+        unsafe {
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                std::mem::size_of_val(data) as gl::types::GLsizeiptr,
+                data.as_ptr() as *const gl::types::GLvoid,
+                gl::STREAM_DRAW,
+            );
+        }
+    }
 }
 
 impl Drop for ArrayBuffer {
@@ -1,5 +1,6 @@
 use glm::{Mat4x4, Vec3};
 
+#[derive(Debug, Clone, Copy)]
 pub struct Camera {
     pub front: Vec3,
     pub pos: Vec3,
@@ -18,5 +19,33 @@ impl Camera {
     pub fn view(&self) -> Mat4x4 {
         glm::look_at(&self.pos, &(self.pos + self.front), &self.up)
     }
+
+    /// Partway from `self` to `other`, for drawing between simulation ticks.
+    pub fn lerp(&self, other: &Camera, t: f32) -> Camera {
+        let front = glm::lerp(&self.front, &other.front, t);
+        Camera {
+            // Opposite fronts would average out to nothing.
+            front: if front.norm() > 0.0 { front.normalize() } else { other.front },
+            pos: glm::lerp(&self.pos, &other.pos, t),
+            up: other.up,
+        }
+    }
+
+    /// Yaw and pitch in degrees for the current `front`, the inverse of
+    /// `front_from_angles`.
+    pub fn angles(&self) -> (f32, f32) {
+        let yaw = self.front.z.atan2(self.front.x).to_degrees();
+        let pitch = self.front.y.clamp(-1.0, 1.0).asin().to_degrees();
+        (yaw, pitch)
+    }
 }
 
+/// Unit vector looking `yaw` degrees around from +x towards +z and `pitch`
+/// degrees up.
+pub fn front_from_angles(yaw: f32, pitch: f32) -> Vec3 {
+    glm::vec3(
+        yaw.to_radians().cos() * pitch.to_radians().cos(),
+        pitch.to_radians().sin(),
+        yaw.to_radians().sin() * pitch.to_radians().cos()
+    ).normalize()
+}
@@ -0,0 +1,295 @@
+use glm::Vec3;
+use super::camera::{self, Camera};
+use super::camera_path::CameraPath;
+use super::collide::{self, AABB};
+use super::controls::{self, Controls};
+use super::input::{Action, Actions};
+
+/// Everything a camera controller might want to look at for a frame.
+pub struct CameraContext<'a> {
+    pub delta_millis: f32,
+    pub actions: &'a Actions,
+    /// Degrees of yaw and pitch to turn this tick.
+    pub look: (f32, f32),
+    pub colliders: &'a [AABB],
+    pub player: &'a Controls,
+    /// Positions the spectator camera can follow.
+    pub targets: &'a [Vec3],
+}
+
+/// Decides where the camera that gets drawn from is each frame.
+pub trait CameraController {
+    fn name(&self) -> &'static str;
+
+    /// Called when switching to this controller, with wherever the camera was
+    /// left by the previous one.
+    fn activate(&mut self, _camera: &Camera) { }
+
+    /// Whether movement input goes to the player while this is active.
+    fn controls_player(&self) -> bool {
+        true
+    }
+
+    fn update(&mut self, camera: &mut Camera, context: &CameraContext);
+}
+
+/// Looks out of the player's eyes.
+pub struct FirstPerson;
+
+impl CameraController for FirstPerson {
+    fn name(&self) -> &'static str {
+        "first person"
+    }
+
+    fn update(&mut self, camera: &mut Camera, context: &CameraContext) {
+        *camera = context.player.camera;
+    }
+}
+
+/// Sits on a boom behind the player's head, looking where they look. The
+/// boom is shortened when there is geometry in the way.
+pub struct ThirdPerson {
+    pub distance: f32,
+    /// How far above the eye the boom is attached.
+    pub height: f32,
+    /// Gap kept between the camera and whatever the boom hit.
+    pub margin: f32,
+    /// Units per second the boom grows back out once the way is clear, it is
+    /// pulled in straight away.
+    pub extend_speed: f32,
+    boom: f32,
+}
+
+impl ThirdPerson {
+    pub fn new() -> ThirdPerson {
+        ThirdPerson {
+            distance: 4.0,
+            height: 0.5,
+            margin: 0.2,
+            extend_speed: 8.0,
+            boom: 4.0,
+        }
+    }
+}
+
+impl CameraController for ThirdPerson {
+    fn name(&self) -> &'static str {
+        "third person"
+    }
+
+    fn update(&mut self, camera: &mut Camera, context: &CameraContext) {
+        let eye = &context.player.camera;
+        let pivot = eye.pos + eye.up * self.height;
+        let back = -eye.front;
+
+        let wanted = boom_length(context.colliders, pivot, back, self.distance, self.margin);
+        if wanted < self.boom {
+            self.boom = wanted;
+        } else {
+            let step = self.extend_speed * context.delta_millis / 1000.0;
+            self.boom = (self.boom + step).min(wanted);
+        }
+
+        camera.pos = pivot + back * self.boom;
+        camera.front = eye.front;
+        camera.up = eye.up;
+    }
+}
+
+/// Flies anywhere, through walls, for poking around levels. The player is
+/// left where they were.
+pub struct Noclip {
+    pub speed: f32,
+    pub sprint_speed: f32,
+    yaw: f32,
+    pitch: f32,
+}
+
+impl Noclip {
+    pub fn new() -> Noclip {
+        Noclip { speed: 15.0, sprint_speed: 40.0, yaw: 90.0, pitch: 0.0 }
+    }
+}
+
+impl CameraController for Noclip {
+    fn name(&self) -> &'static str {
+        "noclip"
+    }
+
+    fn activate(&mut self, camera: &Camera) {
+        let (yaw, pitch) = camera.angles();
+        self.yaw = yaw;
+        self.pitch = pitch;
+    }
+
+    fn controls_player(&self) -> bool {
+        false
+    }
+
+    fn update(&mut self, camera: &mut Camera, context: &CameraContext) {
+        let actions = context.actions;
+        let (yaw, pitch) = context.look;
+        self.yaw += yaw;
+        self.pitch = (self.pitch + pitch).clamp(-89.0, 89.0);
+        camera.front = camera::front_from_angles(self.yaw, self.pitch);
+
+        // Unlike walking this goes wherever we are looking, jump and crouch
+        // fly straight up and down.
+        let amount = |plus, minus| controls::amount(actions, plus) - controls::amount(actions, minus);
+        let right = camera.front.cross(&camera.up).normalize();
+        let mut wish = camera.front * amount(Action::MoveForward, Action::MoveBackward)
+            + right * amount(Action::StrafeRight, Action::StrafeLeft)
+            + camera.up * amount(Action::Jump, Action::Crouch);
+        if wish.norm() > 1.0 {
+            wish = wish.normalize();
+        }
+
+        let speed = if actions.is_down(Action::Sprint) { self.sprint_speed } else { self.speed };
+        camera.pos += wish * speed * context.delta_millis / 1000.0;
+    }
+}
+
+/// Follows one of the context's targets from behind and above, looking at it.
+/// Looking around orbits the target and next target switches to the next one.
+pub struct Spectator {
+    pub distance: f32,
+    pub height: f32,
+    /// How quickly the camera catches up with where it wants to be, per
+    /// second.
+    pub follow_rate: f32,
+    target: usize,
+    yaw: f32,
+}
+
+impl Spectator {
+    pub fn new() -> Spectator {
+        Spectator {
+            distance: 6.0,
+            height: 2.5,
+            follow_rate: 4.0,
+            target: 0,
+            yaw: 90.0,
+        }
+    }
+}
+
+impl CameraController for Spectator {
+    fn name(&self) -> &'static str {
+        "spectator"
+    }
+
+    fn activate(&mut self, camera: &Camera) {
+        self.yaw = camera.angles().0;
+    }
+
+    fn controls_player(&self) -> bool {
+        false
+    }
+
+    fn update(&mut self, camera: &mut Camera, context: &CameraContext) {
+        if context.targets.is_empty() {
+            return;
+        }
+        if context.actions.just_pressed(Action::NextTarget) {
+            self.target += 1;
+        }
+        self.target %= context.targets.len();
+        let target = context.targets[self.target];
+
+        self.yaw += context.look.0;
+        let offset = -camera::front_from_angles(self.yaw, 0.0) * self.distance
+            + camera.up * self.height;
+        let length = offset.norm();
+        let boom = boom_length(context.colliders, target, offset / length, length, 0.2);
+        let wanted = target + offset * (boom / length);
+
+        let t = (self.follow_rate * context.delta_millis / 1000.0).min(1.0);
+        camera.pos = glm::lerp(&camera.pos, &wanted, t);
+        if camera.pos != target {
+            camera.front = (target - camera.pos).normalize();
+        }
+    }
+}
+
+/// Flies along a recorded camera path, for trailers and benchmark runs.
+pub struct PathFollower {
+    pub path: CameraPath,
+    pub looping: bool,
+    time: f32,
+}
+
+impl PathFollower {
+    pub fn new(path: CameraPath) -> PathFollower {
+        PathFollower { path, looping: false, time: 0.0 }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !self.looping && self.time >= self.path.duration()
+    }
+}
+
+impl CameraController for PathFollower {
+    fn name(&self) -> &'static str {
+        "path"
+    }
+
+    fn activate(&mut self, _camera: &Camera) {
+        self.time = 0.0;
+    }
+
+    fn controls_player(&self) -> bool {
+        false
+    }
+
+    fn update(&mut self, camera: &mut Camera, context: &CameraContext) {
+        self.time += context.delta_millis / 1000.0;
+        let duration = self.path.duration();
+        if self.looping && duration > 0.0 {
+            self.time %= duration;
+        }
+        self.path.pose(self.time, camera);
+    }
+}
+
+/// How long a boom from `pivot` along `dir` can be, up to `distance`, while
+/// staying `margin` clear of `colliders`.
+fn boom_length(colliders: &[AABB], pivot: Vec3, dir: Vec3, distance: f32, margin: f32) -> f32 {
+    match collide::ray_cast(colliders, pivot, dir) {
+        Some(hit) if hit < distance + margin => (hit - margin).max(0.0),
+        _ => distance,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    fn third_person_z(colliders: &[super::AABB]) -> f32 {
+        let actions = super::Actions::new(super::super::input::Bindings::defaults());
+        let player = super::Controls::new(super::Camera::new());
+        let context = super::CameraContext {
+            delta_millis: 16.0,
+            actions: &actions,
+            look: (0.0, 0.0),
+            colliders,
+            player: &player,
+            targets: &[],
+        };
+
+        let mut camera = super::Camera::new();
+        let mut third_person = super::ThirdPerson::new();
+        super::CameraController::update(&mut third_person, &mut camera, &context);
+        camera.pos.z
+    }
+
+    #[test]
+    fn test_boom_pulls_in_for_walls() {
+        // The player starts at z 5 looking down +z.
+        assert!((third_person_z(&[]) - 1.0).abs() < 1e-5);
+
+        let wall = super::AABB {
+            left_top_front: glm::vec3(10.0, 10.0, 3.0),
+            right_bottom_back: glm::vec3(0.0, -10.0, 2.0),
+        };
+        assert!((third_person_z(&[wall]) - 3.2).abs() < 1e-5);
+    }
+}
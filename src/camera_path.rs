@@ -0,0 +1,284 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use glm::{Quat, Vec3};
+use super::camera::Camera;
+
+/// Where the camera is at `time` seconds into a path. Orientation is kept as
+/// a quaternion so it can be slerped, roll is always level.
+#[derive(Debug, Clone, Copy)]
+pub struct Keyframe {
+    pub time: f32,
+    pub position: Vec3,
+    pub orientation: Quat,
+}
+
+impl Keyframe {
+    pub fn with_angles(time: f32, position: Vec3, yaw: f32, pitch: f32) -> Keyframe {
+        // Camera yaw turns +x towards +z, which is a negative turn about +y.
+        let orientation = glm::quat_angle_axis(-yaw.to_radians(), &glm::vec3(0.0, 1.0, 0.0))
+            * glm::quat_angle_axis(pitch.to_radians(), &glm::vec3(0.0, 0.0, 1.0));
+        Keyframe { time, position, orientation }
+    }
+
+    pub fn looking_at(time: f32, position: Vec3, target: Vec3) -> Keyframe {
+        let mut camera = Camera::new();
+        camera.front = (target - position).normalize();
+        let (yaw, pitch) = camera.angles();
+        Keyframe::with_angles(time, position, yaw, pitch)
+    }
+
+    pub fn from_camera(time: f32, camera: &Camera) -> Keyframe {
+        let (yaw, pitch) = camera.angles();
+        Keyframe::with_angles(time, camera.pos, yaw, pitch)
+    }
+
+    pub fn front(&self) -> Vec3 {
+        glm::quat_rotate_vec3(&self.orientation, &glm::vec3(1.0, 0.0, 0.0))
+    }
+}
+
+#[derive(Debug)]
+pub enum PathError {
+    Io(io::Error),
+    Malformed(String),
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathError::Io(err) => write!(f, "{}", err),
+            PathError::Malformed(line) => write!(f, "bad keyframe `{}`", line),
+        }
+    }
+}
+
+impl From<io::Error> for PathError {
+    fn from(err: io::Error) -> PathError {
+        PathError::Io(err)
+    }
+}
+
+type Result<T> = std::result::Result<T, PathError>;
+
+/// A fly-through made of keyframes. Position follows a Catmull-Rom spline
+/// through every keyframe and orientation is slerped between neighbours.
+#[derive(Debug, Clone)]
+pub struct CameraPath {
+    keyframes: Vec<Keyframe>,
+}
+
+impl CameraPath {
+    pub fn new() -> CameraPath {
+        CameraPath { keyframes: Vec::new() }
+    }
+
+    pub fn load(path: &str) -> Result<CameraPath> {
+        let src = fs::read_to_string(path)?;
+        CameraPath::parse(&src)
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    /// One keyframe per line, either `<time> <x> <y> <z> angles <yaw> <pitch>`
+    /// or `<time> <x> <y> <z> look_at <x> <y> <z>`, with `#` comments.
+    pub fn parse(src: &str) -> Result<CameraPath> {
+        let mut path = CameraPath::new();
+        for line in src.lines() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let keyframe = parse_keyframe(line)
+                .ok_or_else(|| PathError::Malformed(line.to_string()))?;
+            path.push(keyframe);
+        }
+        Ok(path)
+    }
+
+    /// Keyframes are kept in time order whatever order they are added in.
+    pub fn push(&mut self, keyframe: Keyframe) {
+        let index = self.keyframes
+            .iter()
+            .position(|k| k.time > keyframe.time)
+            .unwrap_or(self.keyframes.len());
+        self.keyframes.insert(index, keyframe);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keyframes.is_empty()
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |k| k.time)
+    }
+
+    /// Position and orientation at `time`, held at the ends outside the path.
+    pub fn sample(&self, time: f32) -> Option<(Vec3, Quat)> {
+        let keys = &self.keyframes;
+        let first = keys.first()?;
+        let last = keys.last()?;
+        if time <= first.time {
+            return Some((first.position, first.orientation));
+        }
+        if time >= last.time {
+            return Some((last.position, last.orientation));
+        }
+
+        let next = keys.iter().position(|k| k.time > time).unwrap();
+        let (k1, k2) = (&keys[next - 1], &keys[next]);
+        // The ends are repeated so the curve still passes through them.
+        let k0 = &keys[next.saturating_sub(2)];
+        let k3 = &keys[(next + 1).min(keys.len() - 1)];
+
+        let t = (time - k1.time) / (k2.time - k1.time);
+        let position = catmull_rom(k0.position, k1.position, k2.position, k3.position, t);
+
+        // Go the short way round.
+        let mut to = k2.orientation;
+        if glm::quat_dot(&k1.orientation, &to) < 0.0 {
+            to = -to;
+        }
+        let orientation = glm::quat_slerp(&k1.orientation, &to, t);
+        Some((position, orientation))
+    }
+
+    /// Moves `camera` to where the path is at `time`.
+    pub fn pose(&self, time: f32, camera: &mut Camera) {
+        if let Some((position, orientation)) = self.sample(time) {
+            camera.pos = position;
+            camera.front = glm::quat_rotate_vec3(&orientation, &glm::vec3(1.0, 0.0, 0.0));
+        }
+    }
+}
+
+impl fmt::Display for CameraPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for keyframe in &self.keyframes {
+            let mut camera = Camera::new();
+            camera.front = keyframe.front();
+            let (yaw, pitch) = camera.angles();
+            let p = keyframe.position;
+            writeln!(f, "{} {} {} {} angles {} {}", keyframe.time, p.x, p.y, p.z, yaw, pitch)?;
+        }
+        Ok(())
+    }
+}
+
+/// Captures keyframes from the live camera every `interval` seconds while
+/// running, for laying out a path by flying it.
+pub struct PathRecorder {
+    pub interval: f32,
+    path: CameraPath,
+    elapsed: f32,
+    since_last: f32,
+}
+
+impl PathRecorder {
+    pub fn new() -> PathRecorder {
+        PathRecorder {
+            interval: 0.5,
+            path: CameraPath::new(),
+            elapsed: 0.0,
+            since_last: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, delta_millis: f32, camera: &Camera) {
+        let delta_secs = delta_millis / 1000.0;
+        self.elapsed += delta_secs;
+        self.since_last += delta_secs;
+        if self.path.is_empty() || self.since_last >= self.interval {
+            self.path.push(Keyframe::from_camera(self.elapsed, camera));
+            self.since_last = 0.0;
+        }
+    }
+
+    /// Stops recording, the camera's final position is always kept.
+    pub fn finish(mut self, camera: &Camera) -> CameraPath {
+        if self.since_last > 0.0 {
+            self.path.push(Keyframe::from_camera(self.elapsed, camera));
+        }
+        self.path
+    }
+}
+
+fn parse_keyframe(line: &str) -> Option<Keyframe> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let number = |i: usize| tokens.get(i)?.parse::<f32>().ok();
+    let time = number(0)?;
+    let position = glm::vec3(number(1)?, number(2)?, number(3)?);
+
+    match (tokens.get(4).cloned()?, tokens.len()) {
+        ("angles", 7) => Some(Keyframe::with_angles(time, position, number(5)?, number(6)?)),
+        ("look_at", 8) => {
+            let target = glm::vec3(number(5)?, number(6)?, number(7)?);
+            Some(Keyframe::looking_at(time, position, target))
+        }
+        _ => None,
+    }
+}
+
+fn catmull_rom(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (p1 * 2.0
+        + (p2 - p0) * t
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+        * 0.5
+}
+
+#[cfg(test)]
+mod tests {
+
+    fn close(a: glm::Vec3, b: glm::Vec3) -> bool {
+        glm::distance(&a, &b) < 1e-4
+    }
+
+    #[test]
+    fn test_passes_through_keyframes() {
+        let path = super::CameraPath::parse("
+            0 0 0 0 angles 0 0
+            1 4 0 0 angles 90 0
+            3 4 0 4 look_at 4 0 10
+        ").unwrap();
+
+        assert_eq!(path.duration(), 3.0);
+        for &(time, expected) in &[
+            (0.0, glm::vec3(0.0, 0.0, 0.0)),
+            (1.0, glm::vec3(4.0, 0.0, 0.0)),
+            (3.0, glm::vec3(4.0, 0.0, 4.0)),
+        ] {
+            let (position, _) = path.sample(time).unwrap();
+            assert!(close(position, expected));
+        }
+    }
+
+    #[test]
+    fn test_orientation_is_slerped() {
+        let mut path = super::CameraPath::new();
+        path.push(super::Keyframe::with_angles(2.0, glm::vec3(0.0, 0.0, 0.0), 90.0, 0.0));
+        path.push(super::Keyframe::with_angles(0.0, glm::vec3(0.0, 0.0, 0.0), 0.0, 0.0));
+
+        let mut camera = super::Camera::new();
+        path.pose(1.0, &mut camera);
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+        assert!(close(camera.front, glm::vec3(half, 0.0, half)));
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        let path = super::CameraPath::parse("0.5 1 2 3 angles 30 -10").unwrap();
+        let reparsed = super::CameraPath::parse(&path.to_string()).unwrap();
+        let (position, orientation) = reparsed.sample(0.5).unwrap();
+        assert!(close(position, glm::vec3(1.0, 2.0, 3.0)));
+        assert!(close(
+            glm::quat_rotate_vec3(&orientation, &glm::vec3(1.0, 0.0, 0.0)),
+            super::super::camera::front_from_angles(30.0, -10.0)
+        ));
+    }
+}
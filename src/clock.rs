@@ -0,0 +1,82 @@
+use std::time::Instant;
+
+/// Simulation always steps by this much, whatever the frame rate, so movement
+/// and physics come out the same on every machine.
+pub const TICK_MILLIS: f32 = 1000.0 / 60.0;
+/// Longer frames than this (a breakpoint, dragging the window) are cut short
+/// instead of being caught up on all at once.
+pub const MAX_FRAME_MILLIS: f32 = 250.0;
+
+/// Hands out fixed simulation ticks to cover however long frames take, and
+/// how far between the last two ticks a frame should be drawn.
+pub struct FrameClock {
+    last_frame: Instant,
+    accumulator: f32,
+}
+
+impl FrameClock {
+    pub fn new() -> FrameClock {
+        FrameClock { last_frame: Instant::now(), accumulator: 0.0 }
+    }
+
+    /// Real time since the last call. `Instant` never goes backwards, unlike
+    /// the wall clock.
+    pub fn frame_millis(&mut self) -> f32 {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_frame);
+        self.last_frame = now;
+        elapsed.as_secs_f32() * 1000.0
+    }
+
+    pub fn accumulate(&mut self, frame_millis: f32) {
+        self.accumulator += frame_millis.clamp(0.0, MAX_FRAME_MILLIS);
+    }
+
+    /// True while there is enough time banked for another tick, taking it.
+    pub fn tick(&mut self) -> bool {
+        if self.accumulator >= TICK_MILLIS {
+            self.accumulator -= TICK_MILLIS;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// How far into the next tick we are, from 0 to 1.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator / TICK_MILLIS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    fn count_ticks(clock: &mut super::FrameClock) -> usize {
+        let mut ticks = 0;
+        while clock.tick() {
+            ticks += 1;
+        }
+        ticks
+    }
+
+    #[test]
+    fn test_ticks_and_alpha() {
+        let mut clock = super::FrameClock::new();
+        clock.accumulate(10.0);
+        assert_eq!(count_ticks(&mut clock), 0);
+        assert!((clock.alpha() - 0.6).abs() < 1e-4);
+
+        clock.accumulate(30.0);
+        assert_eq!(count_ticks(&mut clock), 2);
+        assert!((clock.alpha() - 0.4).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_long_frames_are_clamped() {
+        let mut clock = super::FrameClock::new();
+        clock.accumulate(5000.0);
+        let ticks = count_ticks(&mut clock) as f32;
+        let banked = (ticks + clock.alpha()) * super::TICK_MILLIS;
+        assert!((banked - super::MAX_FRAME_MILLIS).abs() < 1e-3);
+    }
+}
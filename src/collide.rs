@@ -1,4 +1,6 @@
-use glm::Vec3;
+use glm::{Mat4x4, Vec3};
+use super::glm_utils;
+use super::vertex;
 
 #[derive(Debug)]
 pub struct AABB {
@@ -11,7 +13,7 @@ impl AABB {
         let mut left_top_front: Vec3 = glm::vec3(0.0, 0.0, 0.0);
         let mut right_bottom_back: Vec3 = glm::vec3(0.0, 0.0, 0.0);
 
-        for face in verts.chunks(8) {
+        for face in verts.chunks(vertex::VERTEX_FLOATS) {
             left_top_front.x = left_top_front.x.max(face[0]);
             left_top_front.y = left_top_front.y.max(face[1]);
             left_top_front.z = left_top_front.z.max(face[2]);
@@ -26,6 +28,32 @@ impl AABB {
         }
     }
 
+    /// Bounds of this box after `trans`, taken over all eight corners so
+    /// rotated boxes are still fully enclosed.
+    pub fn transformed(&self, trans: &Mat4x4) -> AABB {
+        let min = self.right_bottom_back;
+        let max = self.left_top_front;
+        let first = glm_utils::translate_pos(trans, &min);
+        let mut left_top_front = first;
+        let mut right_bottom_back = first;
+
+        for i in 1..8 {
+            let corner = glm::vec3(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            );
+            let moved = glm_utils::translate_pos(trans, &corner);
+            left_top_front = glm::max2(&left_top_front, &moved);
+            right_bottom_back = glm::min2(&right_bottom_back, &moved);
+        }
+
+        AABB {
+            left_top_front,
+            right_bottom_back,
+        }
+    }
+
     pub fn is_in_aabb(&self, pos: Vec3) -> bool {
         self.right_bottom_back.x <= pos.x
             && self.left_top_front.x >= pos.x
@@ -34,6 +62,77 @@ impl AABB {
             && self.right_bottom_back.y <= pos.y
             && self.left_top_front.y >= pos.y
     }
+
+    pub fn intersects(&self, other: &AABB) -> bool {
+        self.right_bottom_back.x <= other.left_top_front.x
+            && self.left_top_front.x >= other.right_bottom_back.x
+            && self.right_bottom_back.y <= other.left_top_front.y
+            && self.left_top_front.y >= other.right_bottom_back.y
+            && self.right_bottom_back.z <= other.left_top_front.z
+            && self.left_top_front.z >= other.right_bottom_back.z
+    }
+
+    pub fn is_above_xz(&self, pos: Vec3) -> bool {
+        self.right_bottom_back.x <= pos.x
+            && self.left_top_front.x >= pos.x
+            && self.right_bottom_back.z <= pos.z
+            && self.left_top_front.z >= pos.z
+    }
+
+    pub fn top(&self) -> f32 {
+        self.left_top_front.y
+    }
+
+    /// Distance along `dir` from `origin` to where the ray enters the box, or
+    /// zero if it starts inside.
+    pub fn ray_hit(&self, origin: Vec3, dir: Vec3) -> Option<f32> {
+        let mut near = 0.0f32;
+        let mut far = f32::INFINITY;
+        for axis in 0..3 {
+            let min = self.right_bottom_back[axis];
+            let max = self.left_top_front[axis];
+            if dir[axis] == 0.0 {
+                if origin[axis] < min || origin[axis] > max {
+                    return None;
+                }
+                continue;
+            }
+            let a = (min - origin[axis]) / dir[axis];
+            let b = (max - origin[axis]) / dir[axis];
+            near = near.max(a.min(b));
+            far = far.min(a.max(b));
+            if near > far {
+                return None;
+            }
+        }
+        Some(near)
+    }
+}
+
+/// Distance to the closest of `colliders` along the ray, if any are hit.
+pub fn ray_cast(colliders: &[AABB], origin: Vec3, dir: Vec3) -> Option<f32> {
+    colliders
+        .iter()
+        .filter_map(|c| c.ray_hit(origin, dir))
+        .fold(None, |closest, hit| match closest {
+            Some(c) if c <= hit => closest,
+            _ => Some(hit),
+        })
+}
+
+/// Index and height of the highest surface under `pos` whose top is at most
+/// `reach` above it, so ledges shorter than `reach` count as ground to step
+/// onto.
+pub fn ground_under(colliders: &[AABB], pos: Vec3, reach: f32) -> Option<(usize, f32)> {
+    colliders
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.is_above_xz(pos) && c.top() <= pos.y + reach)
+        .map(|(i, c)| (i, c.top()))
+        .fold(None, |highest, (i, top)| match highest {
+            Some((_, h)) if h >= top => highest,
+            _ => Some((i, top)),
+        })
 }
 
 mod tests {
@@ -158,4 +257,71 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_transformed_rotated_box() {
+        let aabb = super::AABB {
+            left_top_front: glm::vec3(1.0, 1.0, 1.0),
+            right_bottom_back: glm::vec3(-1.0, -1.0, -1.0),
+        };
+        let rotation = glm::rotation(
+            std::f32::consts::FRAC_PI_4,
+            &glm::vec3(0.0, 1.0, 0.0)
+        );
+        let moved = glm::translation(&glm::vec3(5.0, 0.0, 0.0)) * rotation;
+
+        let world = aabb.transformed(&moved);
+        let half_diagonal = 2.0_f32.sqrt();
+        assert!((world.left_top_front.x - (5.0 + half_diagonal)).abs() < 1e-5);
+        assert!((world.right_bottom_back.x - (5.0 - half_diagonal)).abs() < 1e-5);
+        assert!((world.left_top_front.z - half_diagonal).abs() < 1e-5);
+        assert!((world.top() - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_ground_under() {
+        let floor = super::AABB {
+            left_top_front: glm::vec3(100.0, 0.0, 100.0),
+            right_bottom_back: glm::vec3(0.0, 0.0, 0.0),
+        };
+        let step = super::AABB {
+            left_top_front: glm::vec3(12.0, 0.5, 12.0),
+            right_bottom_back: glm::vec3(8.0, 0.0, 8.0),
+        };
+        let wall = super::AABB {
+            left_top_front: glm::vec3(22.0, 4.0, 22.0),
+            right_bottom_back: glm::vec3(18.0, 0.0, 18.0),
+        };
+        let colliders = vec![floor, step, wall];
+
+        let open_floor = glm::vec3(5.0, 0.0, 5.0);
+        assert_eq!(super::ground_under(&colliders, open_floor, 0.6), Some((0, 0.0)));
+
+        let on_step = glm::vec3(10.0, 0.0, 10.0);
+        assert_eq!(super::ground_under(&colliders, on_step, 0.6), Some((1, 0.5)));
+
+        let against_wall = glm::vec3(20.0, 0.0, 20.0);
+        assert_eq!(super::ground_under(&colliders, against_wall, 0.6), Some((0, 0.0)));
+
+        let off_map = glm::vec3(-5.0, 0.0, -5.0);
+        assert_eq!(super::ground_under(&colliders, off_map, 0.6), None);
+    }
+
+    #[test]
+    fn test_ray_cast() {
+        let colliders = vec![
+            super::AABB {
+                left_top_front: glm::vec3(1.0, 1.0, 6.0),
+                right_bottom_back: glm::vec3(-1.0, -1.0, 4.0),
+            },
+            super::AABB {
+                left_top_front: glm::vec3(1.0, 1.0, 3.0),
+                right_bottom_back: glm::vec3(-1.0, -1.0, 2.0),
+            },
+        ];
+        let origin = glm::vec3(0.0, 0.0, 0.0);
+
+        assert_eq!(super::ray_cast(&colliders, origin, glm::vec3(0.0, 0.0, 1.0)), Some(2.0));
+        assert_eq!(super::ray_cast(&colliders, origin, glm::vec3(0.0, 0.0, -1.0)), None);
+        assert_eq!(super::ray_cast(&colliders, origin, glm::vec3(0.0, 1.0, 0.0)), None);
+    }
 }
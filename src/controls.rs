@@ -1,95 +1,430 @@
 use super::camera;
-use glfw::{Key, Action};
-use std::collections::HashSet;
-use super::model::Model;
+use super::collide::{self, AABB};
+use super::input::{Action, Actions};
 
-const SENSITIVITY: f32 = 0.5;
-const SPEED: f32 = 0.01;
+const BODY_RADIUS: f32 = 0.3;
+const STEP_HEIGHT: f32 = 0.6;
+// Vertical movement is tuned in units per second rather than per milli.
+const GRAVITY: f32 = 25.0;
+const TERMINAL_VELOCITY: f32 = 50.0;
+const JUMP_SPEED: f32 = 9.0;
+const GROUND_SNAP: f32 = 0.05;
+const COYOTE_MILLIS: f32 = 120.0;
 
-pub struct Controls<'a> {
-    pub camera: &'a mut camera::Camera,
+/// Tunables for walking around, speeds are in units per second.
+#[derive(Debug, Clone, Copy)]
+pub struct Movement {
+    pub walk_speed: f32,
+    pub sprint_speed: f32,
+    pub crouch_speed: f32,
+    /// How quickly we get up to speed while there is input, per second.
+    pub acceleration: f32,
+    /// How quickly we stop on the ground once input is let go, per second.
+    pub friction: f32,
+    /// Fraction of `acceleration` available in the air, there is no friction
+    /// in the air so momentum is kept.
+    pub air_control: f32,
+    pub eye_height: f32,
+    pub crouch_eye_height: f32,
+    /// How fast the eye moves between standing and crouching heights.
+    pub crouch_transition_speed: f32,
+}
+
+impl Movement {
+    pub fn new() -> Movement {
+        Movement {
+            walk_speed: 8.0,
+            sprint_speed: 13.0,
+            crouch_speed: 4.0,
+            acceleration: 60.0,
+            friction: 50.0,
+            air_control: 0.3,
+            eye_height: 2.0,
+            crouch_eye_height: 1.2,
+            crouch_transition_speed: 6.0,
+        }
+    }
+}
 
-    last_x: f32,
-    last_y: f32,
+pub struct Controls {
+    /// The player's eyes, which is not necessarily what gets drawn from.
+    pub camera: camera::Camera,
+    pub movement: Movement,
 
     yaw: f32,
     pitch: f32,
 
-    pressed: HashSet<Key>,
+    // Only x and z are used, vertical movement is handled separately.
+    velocity: glm::Vec3,
+    // Current eye height above the feet, moves towards the standing or
+    // crouching height.
+    eye_height: f32,
+
+    vertical_velocity: f32,
+    on_ground: bool,
+    // Index of the collider we are standing on, if any.
+    ground: Option<usize>,
+    coyote_millis: f32,
 }
 
-impl<'a> Controls<'a> {
-    pub fn new(camera: &'a mut camera::Camera) -> Controls {
+impl Controls {
+    pub fn new(camera: camera::Camera) -> Controls {
+        let movement = Movement::new();
         Controls {
             camera,
-            last_x: 1920.0 / 2.0,
-            last_y: 1080.0 / 2.0,
+            movement,
             yaw: 90.0,
             pitch: 0.0,
-            pressed: HashSet::new(),
+            velocity: glm::vec3(0.0, 0.0, 0.0),
+            eye_height: movement.eye_height,
+            vertical_velocity: 0.0,
+            on_ground: false,
+            ground: None,
+            coyote_millis: 0.0,
         }
     }
 
-    pub fn mouse_callback(&mut self, x: f32, y: f32) {
-        let x_offset = (x - self.last_x) as f32;
-        let y_offset = (self.last_y - y) as f32;
-
-        self.last_x = x;
-        self.last_y = y;
-
-        self.yaw += x_offset * SENSITIVITY;
-        self.pitch += y_offset * SENSITIVITY;
+    fn look(&mut self, (yaw, pitch): (f32, f32)) {
+        self.yaw += yaw;
+        self.pitch += pitch;
         if self.pitch > 89.0 {
             self.pitch = 89.0;
         }
         if self.pitch < -89.0 {
             self.pitch = -89.0;
         }
+        self.camera.front = camera::front_from_angles(self.yaw, self.pitch);
+    }
+
+    /// Box around the player from their feet to just above their eyes.
+    pub fn bounds(&self) -> AABB {
+        let pos = self.camera.pos;
+        AABB {
+            left_top_front: pos + glm::vec3(BODY_RADIUS, 0.2, BODY_RADIUS),
+            right_bottom_back: pos - glm::vec3(BODY_RADIUS, self.eye_height, BODY_RADIUS),
+        }
+    }
+
+    pub fn is_crouching(&self) -> bool {
+        self.eye_height < self.movement.eye_height
+    }
+
+    /// `look` is this tick's yaw and pitch in degrees, from `MouseLook`.
+    pub fn update(
+        &mut self,
+        delta_millis: f32,
+        actions: &Actions,
+        look: (f32, f32),
+        colliders: &[AABB]
+    ) {
+        self.look(look);
+        self.update_crouch(delta_millis, actions, colliders);
+        self.update_horizontal(delta_millis, actions, colliders);
+        self.update_vertical(delta_millis, actions, colliders);
+    }
+
+    fn update_crouch(&mut self, delta_millis: f32, actions: &Actions, colliders: &[AABB]) {
+        let mut target = self.movement.eye_height;
+        if actions.is_down(Action::Crouch) {
+            target = self.movement.crouch_eye_height;
+        }
+        if target > self.eye_height {
+            // Stay down until there is room to stand up.
+            let standing = self.camera.pos + glm::vec3(0.0, target - self.eye_height, 0.0);
+            if is_blocked(colliders, standing, target) {
+                return;
+            }
+        }
 
-        let front = glm::vec3(
-            self.yaw.to_radians().cos() * self.pitch.to_radians().cos(),
-            self.pitch.to_radians().sin(),
-            self.yaw.to_radians().sin() * self.pitch.to_radians().cos()
-            );
-        self.camera.front = front.normalize()
+        let step = self.movement.crouch_transition_speed * delta_millis / 1000.0;
+        let new_height = move_towards(self.eye_height, target, step);
+        // Keep the feet where they are and move the eye.
+        self.camera.pos.y += new_height - self.eye_height;
+        self.eye_height = new_height;
     }
 
-    pub fn update(&mut self, delta_millis: f32, models: &Vec<Model>) {
-        let forward = self.pressed.contains(&Key::W);
-        let backward = self.pressed.contains(&Key::S);
-        let left = self.pressed.contains(&Key::A);
-        let right = self.pressed.contains(&Key::D);
-        let camera_speed = SPEED * delta_millis;
+    fn update_horizontal(&mut self, delta_millis: f32, actions: &Actions, colliders: &[AABB]) {
+        let delta_secs = delta_millis / 1000.0;
+
+        // Walk on the ground plane whatever the pitch, so looking up or down
+        // doesn't slow us down.
+        let forward = glm::vec3(self.camera.front.x, 0.0, self.camera.front.z).normalize();
+        let right = forward.cross(&self.camera.up).normalize();
+
+        // Keys read as 1.0 and sticks anywhere in between, so a half pushed
+        // stick walks at half speed. Diagonals are clamped so they are no
+        // faster than walking straight.
+        let ahead = amount(actions, Action::MoveForward) - amount(actions, Action::MoveBackward);
+        let aside = amount(actions, Action::StrafeRight) - amount(actions, Action::StrafeLeft);
+        let mut wish = forward * ahead + right * aside;
+        if wish.norm() > 1.0 {
+            wish = wish.normalize();
+        }
+
+        let speed = if self.is_crouching() {
+            self.movement.crouch_speed
+        } else if actions.is_down(Action::Sprint) {
+            self.movement.sprint_speed
+        } else {
+            self.movement.walk_speed
+        };
+        let has_input = wish.norm() > 0.0;
+        let rate = match (self.on_ground, has_input) {
+            (true, true) => self.movement.acceleration,
+            (true, false) => self.movement.friction,
+            (false, true) => self.movement.acceleration * self.movement.air_control,
+            (false, false) => 0.0,
+        };
+        let change = wish * speed - self.velocity;
+        let max_change = rate * delta_secs;
+        if change.norm() > max_change {
+            self.velocity += change.normalize() * max_change;
+        } else {
+            self.velocity += change;
+        }
 
-        let mut new_pos = self.camera.pos.clone();
-        if forward {
-            new_pos += camera_speed * self.camera.front;
-        } else if backward {
-            new_pos -= camera_speed * self.camera.front;
+        // Move one axis at a time so we slide along walls instead of
+        // sticking to them.
+        let eye_height = self.eye_height;
+        let mut new_pos = self.camera.pos;
+        new_pos.x += self.velocity.x * delta_secs;
+        if is_blocked(colliders, new_pos, eye_height) {
+            new_pos.x = self.camera.pos.x;
+            self.velocity.x = 0.0;
         }
-        if right {
-            new_pos += self.camera.front.cross(&self.camera.up).normalize() * camera_speed;
-        } else if left {
-            new_pos -= self.camera.front.cross(&self.camera.up).normalize() * camera_speed;
+        new_pos.z += self.velocity.z * delta_secs;
+        if is_blocked(colliders, new_pos, eye_height) {
+            new_pos.z = self.camera.pos.z;
+            self.velocity.z = 0.0;
+        }
+        self.camera.pos = new_pos;
+    }
+
+    /// Moves the player along with collider `index`, which has just moved by
+    /// `delta`: carried when standing on it, pushed when it moves into us.
+    /// Returns false if either would shove us into other geometry, in which
+    /// case the player stays put and the collider should be stopped.
+    pub fn push_by(&mut self, index: usize, delta: glm::Vec3, colliders: &[AABB]) -> bool {
+        let carried = self.ground == Some(index);
+        if !carried && !colliders[index].intersects(&self.bounds()) {
+            return true;
+        }
+
+        let pushed = self.camera.pos + delta;
+        let others = colliders
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != index)
+            .map(|(_, c)| c);
+        if is_blocked(others, pushed, self.eye_height) {
+            return false;
+        }
+        self.camera.pos = pushed;
+        true
+    }
+
+    fn update_vertical(&mut self, delta_millis: f32, actions: &Actions, colliders: &[AABB]) {
+        let delta_secs = delta_millis / 1000.0;
+        let eye_height = self.eye_height;
+        let feet = self.camera.pos - glm::vec3(0.0, eye_height, 0.0);
+        let ground = collide::ground_under(colliders, feet, STEP_HEIGHT);
+
+        if self.on_ground {
+            self.coyote_millis = COYOTE_MILLIS;
+        } else {
+            self.coyote_millis = (self.coyote_millis - delta_millis).max(0.0);
+        }
+
+        let jump = actions.is_down(Action::Jump);
+        if jump && self.coyote_millis > 0.0 && self.vertical_velocity <= 0.0 {
+            self.vertical_velocity = JUMP_SPEED;
+            self.coyote_millis = 0.0;
         }
-        new_pos.y = 2.0;
 
+        self.vertical_velocity = (self.vertical_velocity - GRAVITY * delta_secs)
+            .max(-TERMINAL_VELOCITY);
 
-        let is_colliding = models.into_iter().any(|m| m.collides_with(new_pos));
-        if !is_colliding {
-            self.camera.pos = new_pos;
+        let mut new_pos = self.camera.pos;
+        new_pos.y += self.vertical_velocity * delta_secs;
+        if self.vertical_velocity > 0.0 && is_blocked(colliders, new_pos, eye_height) {
+            // Bumped our head, start falling from where we are.
+            self.vertical_velocity = 0.0;
+            new_pos.y = self.camera.pos.y;
         }
+
+        self.on_ground = false;
+        self.ground = None;
+        if let Some((index, height)) = ground {
+            let landed = new_pos.y - eye_height <= height + GROUND_SNAP;
+            if landed && self.vertical_velocity <= 0.0 {
+                new_pos.y = height + eye_height;
+                self.vertical_velocity = 0.0;
+                self.on_ground = true;
+                self.ground = Some(index);
+            }
+        }
+
+        self.camera.pos = new_pos;
+    }
+}
+
+/// How far `action` is pushed from 0 to 1, for keys and sticks alike.
+pub fn amount(actions: &Actions, action: Action) -> f32 {
+    actions.axis(action).clamp(0.0, 1.0)
+}
+
+fn move_towards(from: f32, to: f32, step: f32) -> f32 {
+    if from < to {
+        (from + step).min(to)
+    } else {
+        (from - step).max(to)
     }
+}
 
-    pub fn key_move_callback(&mut self, key: Key, action: Action) {
-        match action {
-            Action::Press => {
-                self.pressed.insert(key);
+/// Anything shorter than `STEP_HEIGHT` is stepped onto rather than blocking,
+/// so only test from knee height up to the eye.
+fn is_blocked<'b, I>(colliders: I, eye: glm::Vec3, eye_height: f32) -> bool
+where
+    I: IntoIterator<Item = &'b AABB>,
+{
+    let knee = glm::vec3(eye.x, eye.y - eye_height + STEP_HEIGHT, eye.z);
+    colliders.into_iter().any(|c| c.is_in_aabb(knee) || c.is_in_aabb(eye))
+}
+
+#[cfg(test)]
+mod tests {
+
+    fn floor_and_wall() -> Vec<super::AABB> {
+        vec![
+            super::AABB {
+                left_top_front: glm::vec3(100.0, 0.0, 100.0),
+                right_bottom_back: glm::vec3(0.0, 0.0, 0.0),
+            },
+            super::AABB {
+                left_top_front: glm::vec3(10.0, 4.0, 14.0),
+                right_bottom_back: glm::vec3(0.0, 0.0, 10.0),
+            },
+        ]
+    }
+
+    /// Plays `recording` through fresh controls and returns every eye
+    /// position along the way.
+    fn replay(recording: &str) -> Vec<glm::Vec3> {
+        let colliders = floor_and_wall();
+        let mut controls = super::Controls::new(super::camera::Camera::new());
+        let mut actions = super::Actions::new(super::super::input::Bindings::defaults());
+        let mut playback = super::super::input::Playback::parse(recording).unwrap();
+        let mut mouse_look = super::super::input::MouseLook::new();
+
+        let mut path = Vec::new();
+        while let Some(frame) = playback.next_frame() {
+            for event in &frame.events {
+                actions.handle(event);
             }
-            Action::Release => {
-                self.pressed.remove(&key);
+            let look = mouse_look.update(frame.delta_millis, &actions);
+            controls.update(frame.delta_millis, &actions, look, &colliders);
+            actions.end_frame();
+            path.push(controls.camera.pos);
+        }
+        path
+    }
+
+    /// A recording of `count` 16ms frames with `events` happening on frame
+    /// `at`.
+    fn frames(count: usize, at: usize, events: &str) -> String {
+        let mut recording = String::new();
+        for i in 0..count {
+            recording.push_str("0 frame 16\n");
+            if i == at {
+                recording.push_str(events);
+                recording.push('\n');
             }
-            _ => { }
+        }
+        recording
+    }
+
+    #[test]
+    fn test_settles_on_floor_at_eye_height() {
+        let path = replay(&frames(60, 0, ""));
+        assert_eq!(path.last().unwrap().y, 2.0);
+    }
+
+    #[test]
+    fn test_walking_into_wall_stops_at_wall() {
+        let path = replay(&frames(180, 0, "0 key W down"));
+        let end = path.last().unwrap();
+        assert!(end.z < 10.0);
+        assert!(end.z > 9.5);
+        assert_eq!(end.y, 2.0);
+    }
+
+    #[test]
+    fn test_jump_lands_again() {
+        // Give the player a few frames to land from their spawn point.
+        let mut recording = frames(5, 99, "");
+        recording.push_str(&frames(3, 0, "0 key Space down"));
+        recording.push_str(&frames(120, 0, "0 key Space up"));
+        let path = replay(&recording);
+        let highest = path.iter().map(|p| p.y).fold(0.0, f32::max);
+        assert!(highest > 3.0);
+        assert_eq!(path.last().unwrap().y, 2.0);
+    }
+
+    #[test]
+    fn test_diagonal_is_no_faster() {
+        let straight = replay(&frames(30, 5, "0 key W down"));
+        let diagonal = replay(&frames(30, 5, "0 key W down\n0 key D down"));
+        let start = straight[4];
+        let planar = |p: &glm::Vec3| glm::vec2(p.x - start.x, p.z - start.z).norm();
+        let straight = planar(straight.last().unwrap());
+        let diagonal = planar(diagonal.last().unwrap());
+        assert!(straight > 1.0);
+        assert!((straight - diagonal).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_lift_stops_at_ceiling() {
+        let lift = super::AABB {
+            left_top_front: glm::vec3(10.0, 0.0, 10.0),
+            right_bottom_back: glm::vec3(0.0, -0.5, 0.0),
+        };
+        let ceiling = super::AABB {
+            left_top_front: glm::vec3(10.0, 4.0, 10.0),
+            right_bottom_back: glm::vec3(0.0, 3.0, 0.0),
+        };
+        let mut colliders = vec![lift, ceiling];
+        let mut controls = super::Controls::new(super::camera::Camera::new());
+        controls.camera.pos = glm::vec3(5.0, 2.0, 5.0);
+        let actions = super::Actions::new(super::super::input::Bindings::defaults());
+        let mut mouse_look = super::super::input::MouseLook::new();
+        for _ in 0..10 {
+            let look = mouse_look.update(16.0, &actions);
+            controls.update(16.0, &actions, look, &colliders);
+        }
+
+        let raise = |colliders: &mut Vec<super::AABB>, by: f32| {
+            colliders[0].left_top_front.y += by;
+            colliders[0].right_bottom_back.y += by;
+            glm::vec3(0.0, by, 0.0)
         };
+        let up = raise(&mut colliders, 0.5);
+        assert!(controls.push_by(0, up, &colliders));
+        assert_eq!(controls.camera.pos.y, 2.5);
+
+        // Carrying the eye into the ceiling stops the lift instead.
+        let up = raise(&mut colliders, 1.0);
+        assert!(!controls.push_by(0, up, &colliders));
+        assert_eq!(controls.camera.pos.y, 2.5);
+    }
+
+    #[test]
+    fn test_crouch_lowers_eye_and_stands_back_up() {
+        let mut recording = frames(60, 0, "0 key LeftControl down");
+        let crouched = replay(&recording);
+        assert!((crouched.last().unwrap().y - 1.2).abs() < 1e-4);
+
+        recording.push_str(&frames(60, 0, "0 key LeftControl up"));
+        let standing = replay(&recording);
+        assert!((standing.last().unwrap().y - 2.0).abs() < 1e-4);
     }
 }
@@ -0,0 +1,107 @@
+use glfw::{Glfw, Window};
+use glm::Mat4x4;
+use super::settings::{Fullscreen, Settings};
+
+/// Keeps the window in line with the settings: fullscreen, vsync and the
+/// projection for whatever size the framebuffer ends up.
+pub struct Display {
+    pub settings: Settings,
+    fullscreen: bool,
+    // Where the window was before going fullscreen, so we can go back.
+    windowed_pos: (i32, i32),
+    windowed_size: (i32, i32),
+}
+
+impl Display {
+    pub fn new(settings: Settings) -> Display {
+        Display {
+            settings,
+            fullscreen: false,
+            windowed_pos: (0, 0),
+            windowed_size: (settings.width as i32, settings.height as i32),
+        }
+    }
+
+    /// Call once the window's context is current.
+    pub fn apply(&mut self, glfw: &mut Glfw, window: &mut Window) {
+        glfw.set_swap_interval(if self.settings.vsync {
+            glfw::SwapInterval::Sync(1)
+        } else {
+            glfw::SwapInterval::None
+        });
+        self.set_fullscreen(glfw, window, self.settings.fullscreen != Fullscreen::Off);
+    }
+
+    pub fn toggle_fullscreen(&mut self, glfw: &mut Glfw, window: &mut Window) {
+        let fullscreen = !self.fullscreen;
+        self.set_fullscreen(glfw, window, fullscreen);
+    }
+
+    pub fn next_monitor(&mut self, glfw: &mut Glfw, window: &mut Window) {
+        let count = glfw.with_connected_monitors(|_, monitors| monitors.len());
+        if count == 0 {
+            return;
+        }
+        self.settings.monitor = (self.settings.monitor + 1) % count;
+        if self.fullscreen {
+            self.set_fullscreen(glfw, window, true);
+        }
+    }
+
+    fn set_fullscreen(&mut self, glfw: &mut Glfw, window: &mut Window, fullscreen: bool) {
+        if !fullscreen {
+            if self.fullscreen {
+                let (x, y) = self.windowed_pos;
+                let (width, height) = self.windowed_size;
+                window.set_monitor(glfw::WindowMode::Windowed, x, y, width as u32, height as u32, None);
+            }
+            self.fullscreen = false;
+            return;
+        }
+
+        if !self.fullscreen {
+            self.windowed_pos = window.get_pos();
+            self.windowed_size = window.get_size();
+        }
+        let settings = self.settings;
+        self.fullscreen = glfw.with_connected_monitors_mut(|_, monitors| {
+            let monitor = match monitors.get(settings.monitor).or_else(|| monitors.first()) {
+                Some(monitor) => monitor,
+                None => return false,
+            };
+            let mode = match monitor.get_video_mode() {
+                Some(mode) => mode,
+                None => return false,
+            };
+            let (width, height) = match settings.fullscreen {
+                Fullscreen::Exclusive => (settings.width, settings.height),
+                // Matching the monitor's own mode is what gets glfw to skip
+                // the mode switch.
+                _ => (mode.width, mode.height),
+            };
+            window.set_monitor(
+                glfw::WindowMode::FullScreen(monitor),
+                0,
+                0,
+                width,
+                height,
+                Some(mode.refresh_rate)
+            );
+            true
+        });
+    }
+
+    /// Projection for a framebuffer of `width` by `height`, `None` while the
+    /// window is minimised and has no size.
+    pub fn projection(&self, width: i32, height: i32) -> Option<Mat4x4> {
+        if width <= 0 || height <= 0 {
+            return None;
+        }
+        Some(glm::perspective(
+            width as f32 / height as f32,
+            self.settings.fov.to_radians(),
+            0.1,
+            1000.0
+        ))
+    }
+}
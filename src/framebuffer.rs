@@ -0,0 +1,160 @@
+/// Somewhere to render other than the window. Single sampled colour is a
+/// texture that later passes can read; multisampled colour is a renderbuffer
+/// that has to be resolved into a single sampled framebuffer first.
+pub struct Framebuffer {
+    fbo: gl::types::GLuint,
+    color: gl::types::GLuint,
+    depth: Option<gl::types::GLuint>,
+    pub width: i32,
+    pub height: i32,
+    /// 0 when not multisampled.
+    pub samples: i32,
+}
+
+impl Framebuffer {
+    /// `color_format` is a sized internal format such as `gl::RGBA8`.
+    pub fn new(
+        width: i32,
+        height: i32,
+        color_format: gl::types::GLenum,
+        samples: i32,
+        with_depth: bool
+    ) -> Framebuffer {
+        let mut fbo: gl::types::GLuint = 0;
+        let mut color: gl::types::GLuint = 0;
+        let mut depth = None;
+        unsafe {
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+
+            if samples > 0 {
+                gl::GenRenderbuffers(1, &mut color);
+                gl::BindRenderbuffer(gl::RENDERBUFFER, color);
+                gl::RenderbufferStorageMultisample(
+                    gl::RENDERBUFFER,
+                    samples,
+                    color_format,
+                    width,
+                    height
+                );
+                gl::FramebufferRenderbuffer(
+                    gl::FRAMEBUFFER,
+                    gl::COLOR_ATTACHMENT0,
+                    gl::RENDERBUFFER,
+                    color
+                );
+            } else {
+                gl::GenTextures(1, &mut color);
+                gl::BindTexture(gl::TEXTURE_2D, color);
+                gl::TexImage2D(
+                    gl::TEXTURE_2D,
+                    0,
+                    color_format as gl::types::GLint,
+                    width,
+                    height,
+                    0,
+                    gl::RGBA,
+                    gl::FLOAT,
+                    std::ptr::null()
+                );
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+                gl::FramebufferTexture2D(
+                    gl::FRAMEBUFFER,
+                    gl::COLOR_ATTACHMENT0,
+                    gl::TEXTURE_2D,
+                    color,
+                    0
+                );
+            }
+
+            if with_depth {
+                let mut depth_buffer: gl::types::GLuint = 0;
+                gl::GenRenderbuffers(1, &mut depth_buffer);
+                gl::BindRenderbuffer(gl::RENDERBUFFER, depth_buffer);
+                gl::RenderbufferStorageMultisample(
+                    gl::RENDERBUFFER,
+                    samples,
+                    gl::DEPTH_COMPONENT24,
+                    width,
+                    height
+                );
+                gl::FramebufferRenderbuffer(
+                    gl::FRAMEBUFFER,
+                    gl::DEPTH_ATTACHMENT,
+                    gl::RENDERBUFFER,
+                    depth_buffer
+                );
+                depth = Some(depth_buffer);
+            }
+
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                panic!("framebuffer incomplete: {:#x}", status);
+            }
+            gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+
+        Framebuffer { fbo, color, depth, width, height, samples }
+    }
+
+    /// Draws into this framebuffer, with the viewport covering all of it.
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::Viewport(0, 0, self.width, self.height);
+        }
+    }
+
+    /// `None` when multisampled, resolve into another framebuffer to read it.
+    pub fn color_texture(&self) -> Option<gl::types::GLuint> {
+        if self.samples > 0 {
+            None
+        } else {
+            Some(self.color)
+        }
+    }
+
+    /// Copies colour into `target`, averaging the samples if this is
+    /// multisampled.
+    pub fn resolve_into(&self, target: &Framebuffer) {
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.fbo);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, target.fbo);
+            gl::BlitFramebuffer(
+                0, 0, self.width, self.height,
+                0, 0, target.width, target.height,
+                gl::COLOR_BUFFER_BIT,
+                gl::NEAREST
+            );
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe {
+            if self.samples > 0 {
+                gl::DeleteRenderbuffers(1, &self.color);
+            } else {
+                gl::DeleteTextures(1, &self.color);
+            }
+            if let Some(depth) = &self.depth {
+                gl::DeleteRenderbuffers(1, depth);
+            }
+            gl::DeleteFramebuffers(1, &self.fbo);
+        }
+    }
+}
+
+/// Draws into the window again, covering `width` by `height` of it.
+pub fn bind_screen(width: i32, height: i32) {
+    unsafe {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        gl::Viewport(0, 0, width, height);
+    }
+}
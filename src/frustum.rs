@@ -0,0 +1,86 @@
+use glm::{Mat4x4, Vec4};
+use super::collide::AABB;
+
+/// The six planes bounding what a view-projection matrix can see, each as
+/// `(normal, distance)` with the normal pointing inwards.
+pub struct Frustum {
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Pulls the planes straight out of the combined matrix (Gribb and
+    /// Hartmann), so this works for any projection.
+    pub fn from_matrix(view_projection: &Mat4x4) -> Frustum {
+        let m = view_projection;
+        let row = |i: usize| glm::vec4(m[(i, 0)], m[(i, 1)], m[(i, 2)], m[(i, 3)]);
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+
+        let mut planes = [w + x, w - x, w + y, w - y, w + z, w - z];
+        for plane in planes.iter_mut() {
+            let length = glm::vec3(plane.x, plane.y, plane.z).norm();
+            *plane /= length;
+        }
+        Frustum { planes }
+    }
+
+    /// False only when the box is entirely outside one of the planes. Boxes
+    /// near the corners can still pass without being visible, which only
+    /// costs a wasted draw.
+    pub fn intersects_aabb(&self, aabb: &AABB) -> bool {
+        let min = aabb.right_bottom_back;
+        let max = aabb.left_top_front;
+        self.planes.iter().all(|plane| {
+            // The corner furthest along the plane's normal.
+            let corner = glm::vec3(
+                if plane.x >= 0.0 { max.x } else { min.x },
+                if plane.y >= 0.0 { max.y } else { min.y },
+                if plane.z >= 0.0 { max.z } else { min.z },
+            );
+            plane.x * corner.x + plane.y * corner.y + plane.z * corner.z + plane.w >= 0.0
+        })
+    }
+}
+
+/// How many objects made it through culling this frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CullStats {
+    pub drawn: usize,
+    pub culled: usize,
+}
+
+impl CullStats {
+    pub fn new() -> CullStats {
+        CullStats { drawn: 0, culled: 0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    fn cube_at(x: f32, y: f32, z: f32) -> super::AABB {
+        super::AABB {
+            left_top_front: glm::vec3(x + 1.0, y + 1.0, z + 1.0),
+            right_bottom_back: glm::vec3(x - 1.0, y - 1.0, z - 1.0),
+        }
+    }
+
+    #[test]
+    fn test_culls_outside_view() {
+        // Standing at the origin looking down +z.
+        let view = glm::look_at(
+            &glm::vec3(0.0, 0.0, 0.0),
+            &glm::vec3(0.0, 0.0, 1.0),
+            &glm::vec3(0.0, 1.0, 0.0)
+        );
+        let projection = glm::perspective(1.0, std::f32::consts::PI / 3.0, 0.1, 100.0);
+        let frustum = super::Frustum::from_matrix(&(projection * view));
+
+        assert!(frustum.intersects_aabb(&cube_at(0.0, 0.0, 10.0)));
+        // Straddling the edge of the view.
+        assert!(frustum.intersects_aabb(&cube_at(6.0, 0.0, 10.0)));
+
+        assert!(!frustum.intersects_aabb(&cube_at(0.0, 0.0, -10.0)));
+        assert!(!frustum.intersects_aabb(&cube_at(30.0, 0.0, 10.0)));
+        assert!(!frustum.intersects_aabb(&cube_at(0.0, 0.0, 200.0)));
+    }
+}
@@ -0,0 +1,132 @@
+use super::program::Program;
+
+// Material maps use the first three units, nothing else goes through the
+// cache.
+const TRACKED_UNITS: usize = 3;
+
+/// What was asked of the GL this frame and how much of it was avoided.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrawStats {
+    pub draw_calls: usize,
+    pub instances: usize,
+    pub program_binds: usize,
+    pub texture_binds: usize,
+    pub vao_binds: usize,
+    /// Binds skipped because the same thing was already bound.
+    pub skipped_binds: usize,
+}
+
+impl DrawStats {
+    pub fn new() -> DrawStats {
+        DrawStats {
+            draw_calls: 0,
+            instances: 0,
+            program_binds: 0,
+            texture_binds: 0,
+            vao_binds: 0,
+            skipped_binds: 0,
+        }
+    }
+}
+
+/// Remembers the program, VAO and material textures last bound so binding
+/// the same one again costs nothing. Anything bound behind its back goes
+/// unnoticed, so make a new one each frame and only use it for scene
+/// geometry.
+pub struct GlState {
+    program: Option<gl::types::GLuint>,
+    vao: Option<gl::types::GLuint>,
+    textures: [Option<gl::types::GLuint>; TRACKED_UNITS],
+    pub stats: DrawStats,
+}
+
+impl GlState {
+    pub fn new() -> GlState {
+        GlState {
+            program: None,
+            vao: None,
+            textures: [None; TRACKED_UNITS],
+            stats: DrawStats::new(),
+        }
+    }
+
+    pub fn use_program(&mut self, program: &Program) {
+        if self.changes_program(program.id) {
+            program.set_used();
+        }
+    }
+
+    pub fn bind_vertex_array(&mut self, vao: gl::types::GLuint) {
+        if self.changes_vao(vao) {
+            unsafe {
+                gl::BindVertexArray(vao);
+            }
+        }
+    }
+
+    /// Binds a 2D texture to `unit`, which has to be one of the material
+    /// units.
+    pub fn bind_texture(&mut self, unit: u32, texture: gl::types::GLuint) {
+        if self.changes_texture(unit, texture) {
+            unsafe {
+                gl::ActiveTexture(gl::TEXTURE0 + unit);
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+            }
+        }
+    }
+
+    pub fn count_draw(&mut self, instances: usize) {
+        self.stats.draw_calls += 1;
+        self.stats.instances += instances;
+    }
+
+    fn changes_program(&mut self, id: gl::types::GLuint) -> bool {
+        let changed = self.program != Some(id);
+        self.program = Some(id);
+        self.count_bind(changed, |stats| stats.program_binds += 1)
+    }
+
+    fn changes_vao(&mut self, vao: gl::types::GLuint) -> bool {
+        let changed = self.vao != Some(vao);
+        self.vao = Some(vao);
+        self.count_bind(changed, |stats| stats.vao_binds += 1)
+    }
+
+    fn changes_texture(&mut self, unit: u32, texture: gl::types::GLuint) -> bool {
+        let bound = &mut self.textures[unit as usize];
+        let changed = *bound != Some(texture);
+        *bound = Some(texture);
+        self.count_bind(changed, |stats| stats.texture_binds += 1)
+    }
+
+    fn count_bind(&mut self, changed: bool, count: impl FnOnce(&mut DrawStats)) -> bool {
+        if changed {
+            count(&mut self.stats);
+        } else {
+            self.stats.skipped_binds += 1;
+        }
+        changed
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_skips_repeated_binds() {
+        let mut state = super::GlState::new();
+
+        assert!(state.changes_program(1));
+        assert!(!state.changes_program(1));
+        assert!(state.changes_vao(5));
+        assert!(state.changes_texture(0, 7));
+        assert!(state.changes_texture(1, 7));
+        assert!(!state.changes_texture(0, 7));
+        assert!(state.changes_program(2));
+        assert!(!state.changes_vao(5));
+
+        assert_eq!(state.stats.program_binds, 2);
+        assert_eq!(state.stats.vao_binds, 1);
+        assert_eq!(state.stats.texture_binds, 2);
+        assert_eq!(state.stats.skipped_binds, 3);
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::HashSet;
+use super::bindings::{Binding, Bindings, MouseAxis};
+use super::event::{InputEvent, InputState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveForward,
+    MoveBackward,
+    StrafeLeft,
+    StrafeRight,
+    Sprint,
+    Crouch,
+    Jump,
+    Fire,
+    Reload,
+    Quit,
+    CameraMode,
+    NextTarget,
+    /// Mouse style look, the distance to turn this frame.
+    LookX,
+    LookY,
+    /// Stick style look, how fast to turn from -1 to 1.
+    TurnX,
+    TurnY,
+}
+
+const ACTION_NAMES: &[(&str, Action)] = &[
+    ("move_forward", Action::MoveForward),
+    ("move_backward", Action::MoveBackward),
+    ("strafe_left", Action::StrafeLeft),
+    ("strafe_right", Action::StrafeRight),
+    ("sprint", Action::Sprint),
+    ("crouch", Action::Crouch),
+    ("jump", Action::Jump),
+    ("fire", Action::Fire),
+    ("reload", Action::Reload),
+    ("quit", Action::Quit),
+    ("camera_mode", Action::CameraMode),
+    ("next_target", Action::NextTarget),
+    ("look_x", Action::LookX),
+    ("look_y", Action::LookY),
+    ("turn_x", Action::TurnX),
+    ("turn_y", Action::TurnY),
+];
+
+// How far a stick or trigger has to move before its binding counts as held.
+const AXIS_PRESS_THRESHOLD: f32 = 0.5;
+
+impl Action {
+    pub fn from_name(name: &str) -> Option<Action> {
+        ACTION_NAMES.iter().find(|(n, _)| *n == name).map(|(_, a)| *a)
+    }
+}
+
+/// What the player is asking for this frame, in terms of actions rather
+/// than whichever keys or buttons happen to be bound to them.
+pub struct Actions {
+    pub bindings: Bindings,
+    pub state: InputState,
+    was_down: HashSet<Action>,
+}
+
+impl Actions {
+    pub fn new(bindings: Bindings) -> Actions {
+        Actions {
+            bindings,
+            state: InputState::new(),
+            was_down: HashSet::new(),
+        }
+    }
+
+    pub fn handle(&mut self, event: &InputEvent) {
+        self.state.apply(event);
+    }
+
+    pub fn is_down(&self, action: Action) -> bool {
+        self.bindings.for_action(action).any(|binding| self.is_held(binding))
+    }
+
+    /// True only on the frame the action went from up to down, including
+    /// taps that were released again within the frame.
+    pub fn just_pressed(&self, action: Action) -> bool {
+        let tapped = self.bindings.for_action(action).any(|binding| match *binding {
+            Binding::Key(key) => self.state.was_key_pressed(key),
+            Binding::MouseButton(button) => self.state.was_button_pressed(button),
+            Binding::GamepadButton(button) => self.state.was_pad_button_pressed(button),
+            _ => false,
+        });
+        (tapped || self.is_down(action)) && !self.was_down.contains(&action)
+    }
+
+    /// Sum of everything bound to `action` this frame; held keys and buttons
+    /// count as 1.0.
+    pub fn axis(&self, action: Action) -> f32 {
+        let (motion_x, motion_y) = self.state.mouse_motion();
+        self.bindings
+            .for_action(action)
+            .map(|binding| match *binding {
+                Binding::MouseAxis(MouseAxis::X, scale) => motion_x * scale,
+                Binding::MouseAxis(MouseAxis::Y, scale) => motion_y * scale,
+                Binding::GamepadAxis(axis, scale) => self.state.pad_axis(axis) * scale,
+                _ if self.is_held(binding) => 1.0,
+                _ => 0.0,
+            })
+            .sum()
+    }
+
+    /// Clears per-frame state, call once everything has read this frame's
+    /// input.
+    pub fn end_frame(&mut self) {
+        self.was_down = ACTION_NAMES
+            .iter()
+            .map(|(_, action)| *action)
+            .filter(|action| self.is_down(*action))
+            .collect();
+        self.state.end_frame();
+    }
+
+    fn is_held(&self, binding: &Binding) -> bool {
+        match *binding {
+            Binding::Key(key) => self.state.is_key_down(key),
+            Binding::MouseButton(button) => self.state.is_button_down(button),
+            Binding::MouseAxis(_, _) => false,
+            Binding::GamepadButton(button) => self.state.is_pad_button_down(button),
+            Binding::GamepadAxis(axis, scale) => {
+                self.state.pad_axis(axis) * scale > AXIS_PRESS_THRESHOLD
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    fn key(key: super::super::keys::Key, pressed: bool) -> super::InputEvent {
+        super::InputEvent::Key { key, pressed }
+    }
+
+    #[test]
+    fn test_any_binding_holds_action() {
+        let bindings = super::Bindings::parse("move_forward = key:W, key:Up").unwrap();
+        let mut actions = super::Actions::new(bindings);
+
+        actions.handle(&key(super::super::keys::Key::Up, true));
+        assert!(actions.is_down(super::Action::MoveForward));
+        assert!(actions.just_pressed(super::Action::MoveForward));
+
+        actions.end_frame();
+        actions.handle(&key(super::super::keys::Key::W, true));
+        assert!(!actions.just_pressed(super::Action::MoveForward));
+
+        actions.handle(&key(super::super::keys::Key::Up, false));
+        assert!(actions.is_down(super::Action::MoveForward));
+        actions.handle(&key(super::super::keys::Key::W, false));
+        assert!(!actions.is_down(super::Action::MoveForward));
+    }
+
+    #[test]
+    fn test_tap_within_a_frame_is_pressed() {
+        let bindings = super::Bindings::parse("fire = button:Left").unwrap();
+        let mut actions = super::Actions::new(bindings);
+
+        let left = super::super::keys::MouseButton::Left;
+        actions.handle(&super::InputEvent::MouseButton { button: left, pressed: true });
+        actions.handle(&super::InputEvent::MouseButton { button: left, pressed: false });
+        assert!(actions.just_pressed(super::Action::Fire));
+        assert!(!actions.is_down(super::Action::Fire));
+    }
+
+    #[test]
+    fn test_trigger_counts_as_pressed_past_threshold() {
+        let bindings = super::Bindings::parse("fire = axis:pad_right_trigger").unwrap();
+        let mut actions = super::Actions::new(bindings);
+        let trigger = super::super::GamepadAxis::RightTrigger;
+
+        actions.handle(&super::InputEvent::GamepadAxis { axis: trigger, value: 0.3 });
+        assert!(!actions.just_pressed(super::Action::Fire));
+
+        actions.handle(&super::InputEvent::GamepadAxis { axis: trigger, value: 0.9 });
+        assert!(actions.just_pressed(super::Action::Fire));
+
+        actions.end_frame();
+        assert!(actions.is_down(super::Action::Fire));
+        assert!(!actions.just_pressed(super::Action::Fire));
+    }
+
+    #[test]
+    fn test_mouse_axis_skips_first_cursor_event() {
+        let bindings = super::Bindings::parse("look_x = axis:mouse_x\nlook_y = axis:-mouse_y").unwrap();
+        let mut actions = super::Actions::new(bindings);
+
+        actions.handle(&super::InputEvent::CursorMoved { x: 500.0, y: 500.0 });
+        assert_eq!(actions.axis(super::Action::LookX), 0.0);
+
+        actions.handle(&super::InputEvent::CursorMoved { x: 510.0, y: 490.0 });
+        assert_eq!(actions.axis(super::Action::LookX), 10.0);
+        assert_eq!(actions.axis(super::Action::LookY), -10.0);
+
+        actions.end_frame();
+        assert_eq!(actions.axis(super::Action::LookX), 0.0);
+    }
+}
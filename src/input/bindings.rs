@@ -0,0 +1,186 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use super::actions::Action;
+use super::gamepad::{GamepadAxis, GamepadButton};
+use super::keys::{Key, MouseButton};
+
+const DEFAULT_BINDINGS: &str = include_str!("../../assets/bindings.cfg");
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MouseAxis {
+    X,
+    Y,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Binding {
+    Key(Key),
+    MouseButton(MouseButton),
+    /// Motion along the axis multiplied by the scale.
+    MouseAxis(MouseAxis, f32),
+    GamepadButton(GamepadButton),
+    /// Position of the stick or trigger multiplied by the scale.
+    GamepadAxis(GamepadAxis, f32),
+}
+
+#[derive(Debug)]
+pub enum BindingError {
+    Io(io::Error),
+    UnknownAction(String),
+    UnknownBinding(String),
+    Malformed(String),
+}
+
+impl fmt::Display for BindingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BindingError::Io(err) => write!(f, "{}", err),
+            BindingError::UnknownAction(name) => write!(f, "unknown action `{}`", name),
+            BindingError::UnknownBinding(name) => write!(f, "unknown binding `{}`", name),
+            BindingError::Malformed(line) => write!(f, "expected `action = bindings`, got `{}`", line),
+        }
+    }
+}
+
+impl From<io::Error> for BindingError {
+    fn from(err: io::Error) -> BindingError {
+        BindingError::Io(err)
+    }
+}
+
+type Result<T> = std::result::Result<T, BindingError>;
+
+/// Which inputs trigger which actions. An action can have any number of
+/// bindings and an input can be bound to more than one action.
+pub struct Bindings {
+    bindings: Vec<(Action, Binding)>,
+}
+
+impl Bindings {
+    pub fn defaults() -> Bindings {
+        Bindings::parse(DEFAULT_BINDINGS).unwrap()
+    }
+
+    pub fn load(path: &str) -> Result<Bindings> {
+        let src = fs::read_to_string(path)?;
+        Bindings::parse(&src)
+    }
+
+    /// Reads lines of `action = binding, binding`, ignoring blank lines and
+    /// anything after a `#`.
+    pub fn parse(src: &str) -> Result<Bindings> {
+        let mut bindings = Bindings { bindings: Vec::new() };
+
+        for line in src.lines() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut sides = line.splitn(2, '=');
+            let action_name = sides.next().unwrap().trim();
+            let binding_list = sides
+                .next()
+                .ok_or_else(|| BindingError::Malformed(line.to_string()))?;
+            let action = Action::from_name(action_name)
+                .ok_or_else(|| BindingError::UnknownAction(action_name.to_string()))?;
+
+            for binding in binding_list.split(',') {
+                bindings.bind(action, parse_binding(binding.trim())?);
+            }
+        }
+
+        Ok(bindings)
+    }
+
+    pub fn bind(&mut self, action: Action, binding: Binding) {
+        self.bindings.push((action, binding));
+    }
+
+    pub fn for_action(&self, action: Action) -> impl Iterator<Item = &Binding> {
+        self.bindings
+            .iter()
+            .filter(move |(a, _)| *a == action)
+            .map(|(_, binding)| binding)
+    }
+}
+
+fn parse_binding(src: &str) -> Result<Binding> {
+    let unknown = || BindingError::UnknownBinding(src.to_string());
+    let mut parts = src.splitn(2, ':');
+    let kind = parts.next().unwrap().trim();
+    let name = parts.next().ok_or_else(unknown)?.trim();
+
+    match kind {
+        "key" => Key::from_name(name).map(Binding::Key).ok_or_else(unknown),
+        "button" => MouseButton::from_name(name).map(Binding::MouseButton).ok_or_else(unknown),
+        "pad" => GamepadButton::from_name(name).map(Binding::GamepadButton).ok_or_else(unknown),
+        "axis" => {
+            let (scale, axis_name) = match name.strip_prefix('-') {
+                Some(rest) => (-1.0, rest),
+                None => (1.0, name),
+            };
+            match axis_name {
+                "mouse_x" => Ok(Binding::MouseAxis(MouseAxis::X, scale)),
+                "mouse_y" => Ok(Binding::MouseAxis(MouseAxis::Y, scale)),
+                _ => GamepadAxis::from_name(axis_name)
+                    .map(|axis| Binding::GamepadAxis(axis, scale))
+                    .ok_or_else(unknown),
+            }
+        }
+        _ => Err(unknown()),
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_parse_multiple_bindings() {
+        let src = "
+            # comment
+            move_forward = key:Z, key:Up   # azerty
+            fire = button:Left
+            look_y = axis:-mouse_y
+            jump = pad:A
+            turn_x = axis:pad_right_x
+        ";
+        let bindings = super::Bindings::parse(src).unwrap();
+
+        let forward: Vec<_> = bindings.for_action(super::Action::MoveForward).collect();
+        assert_eq!(forward, vec![
+            &super::Binding::Key(super::Key::Z),
+            &super::Binding::Key(super::Key::Up),
+        ]);
+
+        let fire: Vec<_> = bindings.for_action(super::Action::Fire).collect();
+        assert_eq!(fire, vec![&super::Binding::MouseButton(super::MouseButton::Left)]);
+
+        let look: Vec<_> = bindings.for_action(super::Action::LookY).collect();
+        assert_eq!(look, vec![&super::Binding::MouseAxis(super::MouseAxis::Y, -1.0)]);
+
+        let jump: Vec<_> = bindings.for_action(super::Action::Jump).collect();
+        assert_eq!(jump, vec![&super::Binding::GamepadButton(super::GamepadButton::A)]);
+
+        let turn: Vec<_> = bindings.for_action(super::Action::TurnX).collect();
+        assert_eq!(turn, vec![&super::Binding::GamepadAxis(super::GamepadAxis::RightX, 1.0)]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        match super::Bindings::parse("fly = key:W") {
+            Err(super::BindingError::UnknownAction(name)) => assert_eq!(name, "fly"),
+            _ => panic!("expected unknown action"),
+        }
+        match super::Bindings::parse("jump = key:Spacebar") {
+            Err(super::BindingError::UnknownBinding(name)) => assert_eq!(name, "key:Spacebar"),
+            _ => panic!("expected unknown binding"),
+        }
+    }
+
+    #[test]
+    fn test_defaults_parse() {
+        let bindings = super::Bindings::defaults();
+        assert!(bindings.for_action(super::Action::Quit).count() > 0);
+    }
+}
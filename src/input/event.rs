@@ -0,0 +1,256 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use super::gamepad::{GamepadAxis, GamepadButton};
+use super::keys::{Key, MouseButton};
+
+/// Input as the engine sees it, independent of the windowing library that
+/// produced it so it can be recorded, replayed and scripted in tests.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputEvent {
+    Key { key: Key, pressed: bool },
+    MouseButton { button: MouseButton, pressed: bool },
+    CursorMoved { x: f32, y: f32 },
+    /// The window gained or lost focus.
+    Focus { focused: bool },
+    GamepadConnected { connected: bool },
+    GamepadButton { button: GamepadButton, pressed: bool },
+    /// Values have already had dead zones and response curves applied.
+    GamepadAxis { axis: GamepadAxis, value: f32 },
+}
+
+fn up_or_down(pressed: bool) -> &'static str {
+    if pressed { "down" } else { "up" }
+}
+
+impl fmt::Display for InputEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InputEvent::Key { key, pressed } => {
+                write!(f, "key {} {}", key.name(), up_or_down(pressed))
+            }
+            InputEvent::MouseButton { button, pressed } => {
+                write!(f, "button {} {}", button.name(), up_or_down(pressed))
+            }
+            InputEvent::CursorMoved { x, y } => write!(f, "cursor {} {}", x, y),
+            InputEvent::Focus { focused } => {
+                write!(f, "focus {}", if focused { "in" } else { "out" })
+            }
+            InputEvent::GamepadConnected { connected } => {
+                write!(f, "pad {}", if connected { "connected" } else { "disconnected" })
+            }
+            InputEvent::GamepadButton { button, pressed } => {
+                write!(f, "pad_button {} {}", button.name(), up_or_down(pressed))
+            }
+            InputEvent::GamepadAxis { axis, value } => {
+                write!(f, "pad_axis {} {}", axis.name(), value)
+            }
+        }
+    }
+}
+
+impl InputEvent {
+    /// Reads back the format written by `Display`.
+    pub fn parse(src: &str) -> Option<InputEvent> {
+        let mut tokens = src.split_whitespace();
+        let kind = tokens.next()?;
+        let name = tokens.next()?;
+
+        match (kind, name) {
+            ("pad", "connected") => return Some(InputEvent::GamepadConnected { connected: true }),
+            ("pad", "disconnected") => return Some(InputEvent::GamepadConnected { connected: false }),
+            ("focus", "in") => return Some(InputEvent::Focus { focused: true }),
+            ("focus", "out") => return Some(InputEvent::Focus { focused: false }),
+            _ => { }
+        }
+        let last = tokens.next()?;
+
+        let pressed = || match last {
+            "down" => Some(true),
+            "up" => Some(false),
+            _ => None,
+        };
+
+        match kind {
+            "key" => Some(InputEvent::Key { key: Key::from_name(name)?, pressed: pressed()? }),
+            "button" => Some(InputEvent::MouseButton {
+                button: MouseButton::from_name(name)?,
+                pressed: pressed()?,
+            }),
+            "cursor" => Some(InputEvent::CursorMoved {
+                x: name.parse().ok()?,
+                y: last.parse().ok()?,
+            }),
+            "pad_button" => Some(InputEvent::GamepadButton {
+                button: GamepadButton::from_name(name)?,
+                pressed: pressed()?,
+            }),
+            "pad_axis" => Some(InputEvent::GamepadAxis {
+                axis: GamepadAxis::from_name(name)?,
+                value: last.parse().ok()?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Snapshot of held keys and buttons plus everything that happened since
+/// the last `end_frame`.
+pub struct InputState {
+    held_keys: HashSet<Key>,
+    held_buttons: HashSet<MouseButton>,
+    pressed_keys: HashSet<Key>,
+    pressed_buttons: HashSet<MouseButton>,
+    mouse_motion: (f32, f32),
+    last_cursor: Option<(f32, f32)>,
+    held_pad_buttons: HashSet<GamepadButton>,
+    pressed_pad_buttons: HashSet<GamepadButton>,
+    pad_axes: HashMap<GamepadAxis, f32>,
+}
+
+impl InputState {
+    pub fn new() -> InputState {
+        InputState {
+            held_keys: HashSet::new(),
+            held_buttons: HashSet::new(),
+            pressed_keys: HashSet::new(),
+            pressed_buttons: HashSet::new(),
+            mouse_motion: (0.0, 0.0),
+            last_cursor: None,
+            held_pad_buttons: HashSet::new(),
+            pressed_pad_buttons: HashSet::new(),
+            pad_axes: HashMap::new(),
+        }
+    }
+
+    pub fn apply(&mut self, event: &InputEvent) {
+        match *event {
+            InputEvent::Key { key, pressed: true } => {
+                self.held_keys.insert(key);
+                self.pressed_keys.insert(key);
+            }
+            InputEvent::Key { key, pressed: false } => {
+                self.held_keys.remove(&key);
+            }
+            InputEvent::MouseButton { button, pressed: true } => {
+                self.held_buttons.insert(button);
+                self.pressed_buttons.insert(button);
+            }
+            InputEvent::MouseButton { button, pressed: false } => {
+                self.held_buttons.remove(&button);
+            }
+            InputEvent::CursorMoved { x, y } => {
+                // The first position is only a starting point, otherwise
+                // wherever the cursor starts becomes a huge jump.
+                if let Some((last_x, last_y)) = self.last_cursor {
+                    self.mouse_motion.0 += x - last_x;
+                    // Screen y grows downwards, we want up to be positive.
+                    self.mouse_motion.1 += last_y - y;
+                }
+                self.last_cursor = Some((x, y));
+            }
+            InputEvent::Focus { focused } => {
+                // The cursor can be anywhere by the time we get it back, so
+                // start again from its next position.
+                self.last_cursor = None;
+                if !focused {
+                    // We won't hear about anything released while away.
+                    self.held_keys.clear();
+                    self.held_buttons.clear();
+                }
+            }
+            InputEvent::GamepadConnected { .. } => {
+                // Either way nothing from the previous pad is still held.
+                self.held_pad_buttons.clear();
+                self.pad_axes.clear();
+            }
+            InputEvent::GamepadButton { button, pressed: true } => {
+                self.held_pad_buttons.insert(button);
+                self.pressed_pad_buttons.insert(button);
+            }
+            InputEvent::GamepadButton { button, pressed: false } => {
+                self.held_pad_buttons.remove(&button);
+            }
+            InputEvent::GamepadAxis { axis, value } => {
+                self.pad_axes.insert(axis, value);
+            }
+        }
+    }
+
+    pub fn is_key_down(&self, key: Key) -> bool {
+        self.held_keys.contains(&key)
+    }
+
+    pub fn is_button_down(&self, button: MouseButton) -> bool {
+        self.held_buttons.contains(&button)
+    }
+
+    /// Whether the key went down at any point this frame, even if it has
+    /// already been released again.
+    pub fn was_key_pressed(&self, key: Key) -> bool {
+        self.pressed_keys.contains(&key)
+    }
+
+    pub fn was_button_pressed(&self, button: MouseButton) -> bool {
+        self.pressed_buttons.contains(&button)
+    }
+
+    pub fn is_pad_button_down(&self, button: GamepadButton) -> bool {
+        self.held_pad_buttons.contains(&button)
+    }
+
+    pub fn was_pad_button_pressed(&self, button: GamepadButton) -> bool {
+        self.pressed_pad_buttons.contains(&button)
+    }
+
+    /// Unlike mouse motion this is a position, it keeps its value until the
+    /// pad reports a new one.
+    pub fn pad_axis(&self, axis: GamepadAxis) -> f32 {
+        self.pad_axes.get(&axis).cloned().unwrap_or(0.0)
+    }
+
+    pub fn mouse_motion(&self) -> (f32, f32) {
+        self.mouse_motion
+    }
+
+    pub fn end_frame(&mut self) {
+        self.pressed_keys.clear();
+        self.pressed_buttons.clear();
+        self.pressed_pad_buttons.clear();
+        self.mouse_motion = (0.0, 0.0);
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_event_text_round_trip() {
+        let events = vec![
+            super::InputEvent::Key { key: super::Key::LeftShift, pressed: true },
+            super::InputEvent::MouseButton { button: super::MouseButton::Right, pressed: false },
+            super::InputEvent::CursorMoved { x: 512.5, y: -3.0 },
+            super::InputEvent::Focus { focused: false },
+            super::InputEvent::GamepadConnected { connected: false },
+            super::InputEvent::GamepadButton { button: super::GamepadButton::Start, pressed: true },
+            super::InputEvent::GamepadAxis { axis: super::GamepadAxis::RightTrigger, value: 0.25 },
+        ];
+        for event in events {
+            let text = event.to_string();
+            assert_eq!(super::InputEvent::parse(&text), Some(event));
+        }
+        assert_eq!(super::InputEvent::parse("key Spacebar down"), None);
+    }
+
+    #[test]
+    fn test_focus_resets_cursor_and_releases_keys() {
+        let mut state = super::InputState::new();
+        state.apply(&super::InputEvent::CursorMoved { x: 10.0, y: 10.0 });
+        state.apply(&super::InputEvent::Key { key: super::Key::W, pressed: true });
+
+        state.apply(&super::InputEvent::Focus { focused: false });
+        state.apply(&super::InputEvent::Focus { focused: true });
+        state.apply(&super::InputEvent::CursorMoved { x: 500.0, y: 300.0 });
+
+        assert_eq!(state.mouse_motion(), (0.0, 0.0));
+        assert!(!state.is_key_down(super::Key::W));
+    }
+}
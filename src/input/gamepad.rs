@@ -0,0 +1,145 @@
+/// Sticks and triggers. Stick axes run from -1 to 1 with +y pointing down,
+/// triggers from 0 when released to 1 when fully pulled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    A,
+    B,
+    X,
+    Y,
+    LeftBumper,
+    RightBumper,
+    Back,
+    Start,
+    Guide,
+    LeftThumb,
+    RightThumb,
+    DpadUp,
+    DpadRight,
+    DpadDown,
+    DpadLeft,
+}
+
+const AXIS_NAMES: &[(&str, GamepadAxis)] = &[
+    ("pad_left_x", GamepadAxis::LeftX), ("pad_left_y", GamepadAxis::LeftY),
+    ("pad_right_x", GamepadAxis::RightX), ("pad_right_y", GamepadAxis::RightY),
+    ("pad_left_trigger", GamepadAxis::LeftTrigger),
+    ("pad_right_trigger", GamepadAxis::RightTrigger),
+];
+
+const BUTTON_NAMES: &[(&str, GamepadButton)] = &[
+    ("A", GamepadButton::A), ("B", GamepadButton::B), ("X", GamepadButton::X),
+    ("Y", GamepadButton::Y), ("LeftBumper", GamepadButton::LeftBumper),
+    ("RightBumper", GamepadButton::RightBumper), ("Back", GamepadButton::Back),
+    ("Start", GamepadButton::Start), ("Guide", GamepadButton::Guide),
+    ("LeftThumb", GamepadButton::LeftThumb), ("RightThumb", GamepadButton::RightThumb),
+    ("DpadUp", GamepadButton::DpadUp), ("DpadRight", GamepadButton::DpadRight),
+    ("DpadDown", GamepadButton::DpadDown), ("DpadLeft", GamepadButton::DpadLeft),
+];
+
+/// The order of glfw's standard gamepad state, which glfw maps each known
+/// pad's raw axes and buttons onto.
+pub const AXIS_LAYOUT: &[GamepadAxis] = &[
+    GamepadAxis::LeftX, GamepadAxis::LeftY, GamepadAxis::RightX, GamepadAxis::RightY,
+    GamepadAxis::LeftTrigger, GamepadAxis::RightTrigger,
+];
+
+pub const BUTTON_LAYOUT: &[GamepadButton] = &[
+    GamepadButton::A, GamepadButton::B, GamepadButton::X, GamepadButton::Y,
+    GamepadButton::LeftBumper, GamepadButton::RightBumper, GamepadButton::Back,
+    GamepadButton::Start, GamepadButton::Guide, GamepadButton::LeftThumb,
+    GamepadButton::RightThumb, GamepadButton::DpadUp, GamepadButton::DpadRight,
+    GamepadButton::DpadDown, GamepadButton::DpadLeft,
+];
+
+impl GamepadAxis {
+    pub fn from_name(name: &str) -> Option<GamepadAxis> {
+        AXIS_NAMES.iter().find(|(n, _)| *n == name).map(|(_, axis)| *axis)
+    }
+
+    pub fn name(self) -> &'static str {
+        AXIS_NAMES.iter().find(|(_, axis)| *axis == self).unwrap().0
+    }
+}
+
+impl GamepadButton {
+    pub fn from_name(name: &str) -> Option<GamepadButton> {
+        BUTTON_NAMES.iter().find(|(n, _)| *n == name).map(|(_, button)| *button)
+    }
+
+    pub fn name(self) -> &'static str {
+        BUTTON_NAMES.iter().find(|(_, button)| *button == self).unwrap().0
+    }
+}
+
+/// How raw stick positions are turned into input. Anything inside
+/// `dead_zone` reads as centered, the rest is rescaled to start from zero and
+/// raised to `exponent` so small movements give finer control.
+#[derive(Debug, Clone, Copy)]
+pub struct StickResponse {
+    pub dead_zone: f32,
+    pub exponent: f32,
+}
+
+impl StickResponse {
+    pub fn new() -> StickResponse {
+        StickResponse { dead_zone: 0.2, exponent: 2.0 }
+    }
+
+    /// Radial rather than per axis, so diagonals are not snapped to the
+    /// nearest axis near the center.
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        let length = (x * x + y * y).sqrt();
+        if length <= self.dead_zone {
+            return (0.0, 0.0);
+        }
+
+        let live = ((length - self.dead_zone) / (1.0 - self.dead_zone)).min(1.0);
+        let scale = live.powf(self.exponent) / length;
+        (x * scale, y * scale)
+    }
+
+    /// Triggers rest at -1 and are reported from 0 to 1.
+    pub fn apply_trigger(&self, raw: f32) -> f32 {
+        let pulled = (raw + 1.0) / 2.0;
+        if pulled <= self.dead_zone {
+            0.0
+        } else {
+            ((pulled - self.dead_zone) / (1.0 - self.dead_zone)).min(1.0)
+        }
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_dead_zone_and_curve() {
+        let response = super::StickResponse { dead_zone: 0.2, exponent: 2.0 };
+
+        assert_eq!(response.apply(0.1, -0.1), (0.0, 0.0));
+
+        let (x, y) = response.apply(1.0, 0.0);
+        assert!((x - 1.0).abs() < 1e-6);
+        assert_eq!(y, 0.0);
+
+        // Halfway through the live zone is a quarter with a square curve.
+        let (x, _) = response.apply(0.6, 0.0);
+        assert!((x - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_trigger_rest_is_zero() {
+        let response = super::StickResponse::new();
+        assert_eq!(response.apply_trigger(-1.0), 0.0);
+        assert_eq!(response.apply_trigger(1.0), 1.0);
+    }
+}
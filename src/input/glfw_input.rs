@@ -0,0 +1,297 @@
+use super::event::InputEvent;
+use super::gamepad::{StickResponse, AXIS_LAYOUT, BUTTON_LAYOUT};
+use super::keys::{Key, MouseButton};
+use std::os::raw::{c_int, c_uchar};
+
+const JOYSTICKS: &[glfw::JoystickId] = &[
+    glfw::JoystickId::Joystick1, glfw::JoystickId::Joystick2, glfw::JoystickId::Joystick3,
+    glfw::JoystickId::Joystick4, glfw::JoystickId::Joystick5, glfw::JoystickId::Joystick6,
+    glfw::JoystickId::Joystick7, glfw::JoystickId::Joystick8, glfw::JoystickId::Joystick9,
+    glfw::JoystickId::Joystick10, glfw::JoystickId::Joystick11, glfw::JoystickId::Joystick12,
+    glfw::JoystickId::Joystick13, glfw::JoystickId::Joystick14, glfw::JoystickId::Joystick15,
+    glfw::JoystickId::Joystick16,
+];
+
+/// Converts the window events we care about into engine input events,
+/// everything else is left for `main` to deal with.
+pub fn translate(event: &glfw::WindowEvent) -> Option<InputEvent> {
+    match *event {
+        glfw::WindowEvent::Key(key, _, action, _) => {
+            let pressed = is_pressed(action)?;
+            Some(InputEvent::Key { key: key_from_glfw(key)?, pressed })
+        }
+        glfw::WindowEvent::MouseButton(button, action, _) => {
+            let pressed = is_pressed(action)?;
+            Some(InputEvent::MouseButton { button: button_from_glfw(button), pressed })
+        }
+        glfw::WindowEvent::CursorPos(x, y) => {
+            Some(InputEvent::CursorMoved { x: x as f32, y: y as f32 })
+        }
+        glfw::WindowEvent::Focus(focused) => Some(InputEvent::Focus { focused }),
+        _ => None,
+    }
+}
+
+// GLFW_RAW_MOUSE_MOTION, which the glfw crate doesn't know about yet.
+const RAW_MOUSE_MOTION: c_int = 0x0003_3005;
+
+/// GLFWgamepadstate, in the standard layout of `BUTTON_LAYOUT` and
+/// `AXIS_LAYOUT`.
+#[repr(C)]
+struct GamepadState {
+    buttons: [c_uchar; 15],
+    axes: [f32; 6],
+}
+
+// Glfw 3.3 functions the glfw crate doesn't bind yet. Only call them after
+// checking `has_glfw_3_3`.
+extern "C" {
+    fn glfwRawMouseMotionSupported() -> c_int;
+    fn glfwJoystickIsGamepad(jid: c_int) -> c_int;
+    fn glfwGetGamepadState(jid: c_int, state: *mut GamepadState) -> c_int;
+}
+
+fn has_glfw_3_3() -> bool {
+    let version = glfw::get_version();
+    (version.major, version.minor) >= (3, 3)
+}
+
+/// Asks for mouse motion without the OS's pointer acceleration. Needs glfw
+/// 3.3, a platform that supports it and the cursor disabled, returns whether
+/// it could be turned on.
+pub fn set_raw_mouse_motion(window: &mut glfw::Window, enabled: bool) -> bool {
+    if !has_glfw_3_3() || unsafe { glfwRawMouseMotionSupported() } == 0 {
+        return false;
+    }
+    unsafe {
+        glfw::ffi::glfwSetInputMode(
+            glfw::Context::window_ptr(window),
+            RAW_MOUSE_MOTION,
+            enabled as c_int
+        );
+    }
+    true
+}
+
+/// Glfw only reports joysticks when asked, so this polls the first gamepad
+/// plugged in once a frame and turns whatever changed into input events.
+/// Unplugging it releases everything and the next pad plugged in is picked up.
+/// Only joysticks glfw has a gamepad mapping for are used, so needs glfw 3.3.
+pub struct GamepadPoller {
+    pub response: StickResponse,
+    joystick: Option<glfw::JoystickId>,
+    axes: Vec<f32>,
+    buttons: Vec<bool>,
+}
+
+impl GamepadPoller {
+    pub fn new(response: StickResponse) -> GamepadPoller {
+        GamepadPoller {
+            response,
+            joystick: None,
+            axes: vec![0.0; AXIS_LAYOUT.len()],
+            buttons: vec![false; BUTTON_LAYOUT.len()],
+        }
+    }
+
+    pub fn poll(&mut self, events: &mut Vec<InputEvent>) {
+        if !has_glfw_3_3() {
+            return;
+        }
+
+        let mut state = GamepadState { buttons: [0; 15], axes: [0.0; 6] };
+        let read = |id: glfw::JoystickId, state: &mut GamepadState| unsafe {
+            glfwJoystickIsGamepad(id as c_int) != 0 && glfwGetGamepadState(id as c_int, state) != 0
+        };
+
+        if self.joystick.is_some_and(|id| !read(id, &mut state)) {
+            self.joystick = None;
+            self.axes.iter_mut().for_each(|axis| *axis = 0.0);
+            self.buttons.iter_mut().for_each(|button| *button = false);
+            events.push(InputEvent::GamepadConnected { connected: false });
+        }
+        if self.joystick.is_none() {
+            self.joystick = JOYSTICKS.iter().cloned().find(|id| read(*id, &mut state));
+            if self.joystick.is_none() {
+                return;
+            }
+            events.push(InputEvent::GamepadConnected { connected: true });
+        }
+
+        let axes = state.axes;
+        let (left_x, left_y) = self.response.apply(axes[0], axes[1]);
+        let (right_x, right_y) = self.response.apply(axes[2], axes[3]);
+        let values = [
+            left_x,
+            left_y,
+            right_x,
+            right_y,
+            self.response.apply_trigger(axes[4]),
+            self.response.apply_trigger(axes[5]),
+        ];
+        for ((axis, old), value) in AXIS_LAYOUT.iter().zip(self.axes.iter_mut()).zip(&values) {
+            if *old != *value {
+                *old = *value;
+                events.push(InputEvent::GamepadAxis { axis: *axis, value: *value });
+            }
+        }
+
+        let buttons = BUTTON_LAYOUT.iter().zip(self.buttons.iter_mut()).zip(&state.buttons);
+        for ((button, old), raw) in buttons {
+            let pressed = *raw as i32 == glfw::Action::Press as i32;
+            if *old != pressed {
+                *old = pressed;
+                events.push(InputEvent::GamepadButton { button: *button, pressed });
+            }
+        }
+    }
+}
+
+/// Key repeats are dropped, we only track up and down.
+fn is_pressed(action: glfw::Action) -> Option<bool> {
+    match action {
+        glfw::Action::Press => Some(true),
+        glfw::Action::Release => Some(false),
+        glfw::Action::Repeat => None,
+    }
+}
+
+fn button_from_glfw(button: glfw::MouseButton) -> MouseButton {
+    match button {
+        glfw::MouseButton::Button1 => MouseButton::Left,
+        glfw::MouseButton::Button2 => MouseButton::Right,
+        glfw::MouseButton::Button3 => MouseButton::Middle,
+        glfw::MouseButton::Button4 => MouseButton::Button4,
+        glfw::MouseButton::Button5 => MouseButton::Button5,
+        glfw::MouseButton::Button6 => MouseButton::Button6,
+        glfw::MouseButton::Button7 => MouseButton::Button7,
+        glfw::MouseButton::Button8 => MouseButton::Button8,
+    }
+}
+
+fn key_from_glfw(key: glfw::Key) -> Option<Key> {
+    let key = match key {
+        glfw::Key::Space => Key::Space,
+        glfw::Key::Apostrophe => Key::Apostrophe,
+        glfw::Key::Comma => Key::Comma,
+        glfw::Key::Minus => Key::Minus,
+        glfw::Key::Period => Key::Period,
+        glfw::Key::Slash => Key::Slash,
+        glfw::Key::Num0 => Key::Num0,
+        glfw::Key::Num1 => Key::Num1,
+        glfw::Key::Num2 => Key::Num2,
+        glfw::Key::Num3 => Key::Num3,
+        glfw::Key::Num4 => Key::Num4,
+        glfw::Key::Num5 => Key::Num5,
+        glfw::Key::Num6 => Key::Num6,
+        glfw::Key::Num7 => Key::Num7,
+        glfw::Key::Num8 => Key::Num8,
+        glfw::Key::Num9 => Key::Num9,
+        glfw::Key::Semicolon => Key::Semicolon,
+        glfw::Key::Equal => Key::Equal,
+        glfw::Key::A => Key::A,
+        glfw::Key::B => Key::B,
+        glfw::Key::C => Key::C,
+        glfw::Key::D => Key::D,
+        glfw::Key::E => Key::E,
+        glfw::Key::F => Key::F,
+        glfw::Key::G => Key::G,
+        glfw::Key::H => Key::H,
+        glfw::Key::I => Key::I,
+        glfw::Key::J => Key::J,
+        glfw::Key::K => Key::K,
+        glfw::Key::L => Key::L,
+        glfw::Key::M => Key::M,
+        glfw::Key::N => Key::N,
+        glfw::Key::O => Key::O,
+        glfw::Key::P => Key::P,
+        glfw::Key::Q => Key::Q,
+        glfw::Key::R => Key::R,
+        glfw::Key::S => Key::S,
+        glfw::Key::T => Key::T,
+        glfw::Key::U => Key::U,
+        glfw::Key::V => Key::V,
+        glfw::Key::W => Key::W,
+        glfw::Key::X => Key::X,
+        glfw::Key::Y => Key::Y,
+        glfw::Key::Z => Key::Z,
+        glfw::Key::LeftBracket => Key::LeftBracket,
+        glfw::Key::Backslash => Key::Backslash,
+        glfw::Key::RightBracket => Key::RightBracket,
+        glfw::Key::GraveAccent => Key::GraveAccent,
+        glfw::Key::World1 => Key::World1,
+        glfw::Key::World2 => Key::World2,
+        glfw::Key::Escape => Key::Escape,
+        glfw::Key::Enter => Key::Enter,
+        glfw::Key::Tab => Key::Tab,
+        glfw::Key::Backspace => Key::Backspace,
+        glfw::Key::Insert => Key::Insert,
+        glfw::Key::Delete => Key::Delete,
+        glfw::Key::Right => Key::Right,
+        glfw::Key::Left => Key::Left,
+        glfw::Key::Down => Key::Down,
+        glfw::Key::Up => Key::Up,
+        glfw::Key::PageUp => Key::PageUp,
+        glfw::Key::PageDown => Key::PageDown,
+        glfw::Key::Home => Key::Home,
+        glfw::Key::End => Key::End,
+        glfw::Key::CapsLock => Key::CapsLock,
+        glfw::Key::ScrollLock => Key::ScrollLock,
+        glfw::Key::NumLock => Key::NumLock,
+        glfw::Key::PrintScreen => Key::PrintScreen,
+        glfw::Key::Pause => Key::Pause,
+        glfw::Key::F1 => Key::F1,
+        glfw::Key::F2 => Key::F2,
+        glfw::Key::F3 => Key::F3,
+        glfw::Key::F4 => Key::F4,
+        glfw::Key::F5 => Key::F5,
+        glfw::Key::F6 => Key::F6,
+        glfw::Key::F7 => Key::F7,
+        glfw::Key::F8 => Key::F8,
+        glfw::Key::F9 => Key::F9,
+        glfw::Key::F10 => Key::F10,
+        glfw::Key::F11 => Key::F11,
+        glfw::Key::F12 => Key::F12,
+        glfw::Key::F13 => Key::F13,
+        glfw::Key::F14 => Key::F14,
+        glfw::Key::F15 => Key::F15,
+        glfw::Key::F16 => Key::F16,
+        glfw::Key::F17 => Key::F17,
+        glfw::Key::F18 => Key::F18,
+        glfw::Key::F19 => Key::F19,
+        glfw::Key::F20 => Key::F20,
+        glfw::Key::F21 => Key::F21,
+        glfw::Key::F22 => Key::F22,
+        glfw::Key::F23 => Key::F23,
+        glfw::Key::F24 => Key::F24,
+        glfw::Key::F25 => Key::F25,
+        glfw::Key::Kp0 => Key::Kp0,
+        glfw::Key::Kp1 => Key::Kp1,
+        glfw::Key::Kp2 => Key::Kp2,
+        glfw::Key::Kp3 => Key::Kp3,
+        glfw::Key::Kp4 => Key::Kp4,
+        glfw::Key::Kp5 => Key::Kp5,
+        glfw::Key::Kp6 => Key::Kp6,
+        glfw::Key::Kp7 => Key::Kp7,
+        glfw::Key::Kp8 => Key::Kp8,
+        glfw::Key::Kp9 => Key::Kp9,
+        glfw::Key::KpDecimal => Key::KpDecimal,
+        glfw::Key::KpDivide => Key::KpDivide,
+        glfw::Key::KpMultiply => Key::KpMultiply,
+        glfw::Key::KpSubtract => Key::KpSubtract,
+        glfw::Key::KpAdd => Key::KpAdd,
+        glfw::Key::KpEnter => Key::KpEnter,
+        glfw::Key::KpEqual => Key::KpEqual,
+        glfw::Key::LeftShift => Key::LeftShift,
+        glfw::Key::LeftControl => Key::LeftControl,
+        glfw::Key::LeftAlt => Key::LeftAlt,
+        glfw::Key::LeftSuper => Key::LeftSuper,
+        glfw::Key::RightShift => Key::RightShift,
+        glfw::Key::RightControl => Key::RightControl,
+        glfw::Key::RightAlt => Key::RightAlt,
+        glfw::Key::RightSuper => Key::RightSuper,
+        glfw::Key::Menu => Key::Menu,
+        glfw::Key::Unknown => return None,
+    };
+    Some(key)
+}
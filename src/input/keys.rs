@@ -0,0 +1,90 @@
+/// Keyboard keys, named after their position on a US layout the same way
+/// glfw does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    Space, Apostrophe, Comma, Minus, Period, Slash, Num0, Num1, Num2, Num3, Num4, Num5,
+    Num6, Num7, Num8, Num9, Semicolon, Equal, A, B, C, D, E, F, G, H, I, J, K, L, M, N, O,
+    P, Q, R, S, T, U, V, W, X, Y, Z, LeftBracket, Backslash, RightBracket, GraveAccent,
+    World1, World2, Escape, Enter, Tab, Backspace, Insert, Delete, Right, Left, Down, Up,
+    PageUp, PageDown, Home, End, CapsLock, ScrollLock, NumLock, PrintScreen, Pause, F1, F2,
+    F3, F4, F5, F6, F7, F8, F9, F10, F11, F12, F13, F14, F15, F16, F17, F18, F19, F20, F21,
+    F22, F23, F24, F25, Kp0, Kp1, Kp2, Kp3, Kp4, Kp5, Kp6, Kp7, Kp8, Kp9, KpDecimal,
+    KpDivide, KpMultiply, KpSubtract, KpAdd, KpEnter, KpEqual, LeftShift, LeftControl,
+    LeftAlt, LeftSuper, RightShift, RightControl, RightAlt, RightSuper, Menu,
+}
+
+const KEY_NAMES: &[(&str, Key)] = &[
+    ("Space", Key::Space), ("Apostrophe", Key::Apostrophe), ("Comma", Key::Comma),
+    ("Minus", Key::Minus), ("Period", Key::Period), ("Slash", Key::Slash),
+    ("Num0", Key::Num0), ("Num1", Key::Num1), ("Num2", Key::Num2), ("Num3", Key::Num3),
+    ("Num4", Key::Num4), ("Num5", Key::Num5), ("Num6", Key::Num6), ("Num7", Key::Num7),
+    ("Num8", Key::Num8), ("Num9", Key::Num9), ("Semicolon", Key::Semicolon),
+    ("Equal", Key::Equal), ("A", Key::A), ("B", Key::B), ("C", Key::C), ("D", Key::D),
+    ("E", Key::E), ("F", Key::F), ("G", Key::G), ("H", Key::H), ("I", Key::I),
+    ("J", Key::J), ("K", Key::K), ("L", Key::L), ("M", Key::M), ("N", Key::N),
+    ("O", Key::O), ("P", Key::P), ("Q", Key::Q), ("R", Key::R), ("S", Key::S),
+    ("T", Key::T), ("U", Key::U), ("V", Key::V), ("W", Key::W), ("X", Key::X),
+    ("Y", Key::Y), ("Z", Key::Z), ("LeftBracket", Key::LeftBracket),
+    ("Backslash", Key::Backslash), ("RightBracket", Key::RightBracket),
+    ("GraveAccent", Key::GraveAccent), ("World1", Key::World1), ("World2", Key::World2),
+    ("Escape", Key::Escape), ("Enter", Key::Enter), ("Tab", Key::Tab),
+    ("Backspace", Key::Backspace), ("Insert", Key::Insert), ("Delete", Key::Delete),
+    ("Right", Key::Right), ("Left", Key::Left), ("Down", Key::Down), ("Up", Key::Up),
+    ("PageUp", Key::PageUp), ("PageDown", Key::PageDown), ("Home", Key::Home),
+    ("End", Key::End), ("CapsLock", Key::CapsLock), ("ScrollLock", Key::ScrollLock),
+    ("NumLock", Key::NumLock), ("PrintScreen", Key::PrintScreen), ("Pause", Key::Pause),
+    ("F1", Key::F1), ("F2", Key::F2), ("F3", Key::F3), ("F4", Key::F4), ("F5", Key::F5),
+    ("F6", Key::F6), ("F7", Key::F7), ("F8", Key::F8), ("F9", Key::F9), ("F10", Key::F10),
+    ("F11", Key::F11), ("F12", Key::F12), ("F13", Key::F13), ("F14", Key::F14),
+    ("F15", Key::F15), ("F16", Key::F16), ("F17", Key::F17), ("F18", Key::F18),
+    ("F19", Key::F19), ("F20", Key::F20), ("F21", Key::F21), ("F22", Key::F22),
+    ("F23", Key::F23), ("F24", Key::F24), ("F25", Key::F25), ("Kp0", Key::Kp0),
+    ("Kp1", Key::Kp1), ("Kp2", Key::Kp2), ("Kp3", Key::Kp3), ("Kp4", Key::Kp4),
+    ("Kp5", Key::Kp5), ("Kp6", Key::Kp6), ("Kp7", Key::Kp7), ("Kp8", Key::Kp8),
+    ("Kp9", Key::Kp9), ("KpDecimal", Key::KpDecimal), ("KpDivide", Key::KpDivide),
+    ("KpMultiply", Key::KpMultiply), ("KpSubtract", Key::KpSubtract),
+    ("KpAdd", Key::KpAdd), ("KpEnter", Key::KpEnter), ("KpEqual", Key::KpEqual),
+    ("LeftShift", Key::LeftShift), ("LeftControl", Key::LeftControl),
+    ("LeftAlt", Key::LeftAlt), ("LeftSuper", Key::LeftSuper),
+    ("RightShift", Key::RightShift), ("RightControl", Key::RightControl),
+    ("RightAlt", Key::RightAlt), ("RightSuper", Key::RightSuper), ("Menu", Key::Menu),
+];
+
+impl Key {
+    pub fn from_name(name: &str) -> Option<Key> {
+        KEY_NAMES.iter().find(|(n, _)| *n == name).map(|(_, key)| *key)
+    }
+
+    pub fn name(self) -> &'static str {
+        KEY_NAMES.iter().find(|(_, key)| *key == self).unwrap().0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Button4,
+    Button5,
+    Button6,
+    Button7,
+    Button8,
+}
+
+const BUTTON_NAMES: &[(&str, MouseButton)] = &[
+    ("Left", MouseButton::Left), ("Right", MouseButton::Right),
+    ("Middle", MouseButton::Middle), ("4", MouseButton::Button4),
+    ("5", MouseButton::Button5), ("6", MouseButton::Button6), ("7", MouseButton::Button7),
+    ("8", MouseButton::Button8),
+];
+
+impl MouseButton {
+    pub fn from_name(name: &str) -> Option<MouseButton> {
+        BUTTON_NAMES.iter().find(|(n, _)| *n == name).map(|(_, button)| *button)
+    }
+
+    pub fn name(self) -> &'static str {
+        BUTTON_NAMES.iter().find(|(_, button)| *button == self).unwrap().0
+    }
+}
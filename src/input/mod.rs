@@ -0,0 +1,16 @@
+mod actions;
+mod bindings;
+mod event;
+mod gamepad;
+mod glfw_input;
+mod keys;
+mod mouse_look;
+mod record;
+
+pub use self::actions::*;
+pub use self::bindings::*;
+pub use self::event::*;
+pub use self::gamepad::*;
+pub use self::glfw_input::*;
+pub use self::mouse_look::*;
+pub use self::record::*;
@@ -0,0 +1,92 @@
+use super::actions::{Action, Actions};
+
+/// Turns look input into degrees of yaw and pitch each tick. Mouse motion is
+/// scaled by the sensitivities, sticks turn at `turn_speed`.
+#[derive(Debug, Clone, Copy)]
+pub struct MouseLook {
+    /// Degrees per unit of mouse motion.
+    pub sensitivity_x: f32,
+    pub sensitivity_y: f32,
+    pub invert_y: bool,
+    /// From 0, where each tick's motion is used as is, towards 1, where more
+    /// of the previous ticks are blended in.
+    pub smoothing: f32,
+    /// Extra gain per unit of mouse speed in motion per milli, 0 turns it off.
+    pub acceleration: f32,
+    /// Degrees per second with a stick pushed all the way.
+    pub turn_speed: f32,
+    smoothed: (f32, f32),
+}
+
+impl MouseLook {
+    pub fn new() -> MouseLook {
+        MouseLook {
+            sensitivity_x: 0.5,
+            sensitivity_y: 0.5,
+            invert_y: false,
+            smoothing: 0.0,
+            acceleration: 0.0,
+            turn_speed: 180.0,
+            smoothed: (0.0, 0.0),
+        }
+    }
+
+    /// Call once a tick, before anything looks around.
+    pub fn update(&mut self, delta_millis: f32, actions: &Actions) -> (f32, f32) {
+        let (mut x, mut y) = (actions.axis(Action::LookX), actions.axis(Action::LookY));
+        if self.acceleration > 0.0 && delta_millis > 0.0 {
+            let speed = (x * x + y * y).sqrt() / delta_millis;
+            let gain = 1.0 + self.acceleration * speed;
+            x *= gain;
+            y *= gain;
+        }
+
+        let keep = self.smoothing.clamp(0.0, 0.99);
+        self.smoothed = (
+            x * (1.0 - keep) + self.smoothed.0 * keep,
+            y * (1.0 - keep) + self.smoothed.1 * keep,
+        );
+
+        let turn = self.turn_speed * delta_millis / 1000.0;
+        let yaw = self.smoothed.0 * self.sensitivity_x + actions.axis(Action::TurnX) * turn;
+        let pitch = self.smoothed.1 * self.sensitivity_y + actions.axis(Action::TurnY) * turn;
+        (yaw, if self.invert_y { -pitch } else { pitch })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    fn moved_actions(x: f32, y: f32) -> super::Actions {
+        let bindings = super::super::Bindings::parse("look_x = axis:mouse_x\nlook_y = axis:mouse_y");
+        let mut actions = super::Actions::new(bindings.unwrap());
+        actions.handle(&super::super::InputEvent::CursorMoved { x: 0.0, y: 0.0 });
+        actions.handle(&super::super::InputEvent::CursorMoved { x, y });
+        actions
+    }
+
+    #[test]
+    fn test_sensitivity_and_invert() {
+        let mut look = super::MouseLook::new();
+        look.sensitivity_x = 0.2;
+        look.sensitivity_y = 0.1;
+        look.invert_y = true;
+
+        // Screen y grows downwards so moving the mouse up is negative.
+        let (yaw, pitch) = look.update(16.0, &moved_actions(10.0, -20.0));
+        assert!((yaw - 2.0).abs() < 1e-5);
+        assert!((pitch + 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_smoothing_spreads_motion() {
+        let mut look = super::MouseLook::new();
+        look.sensitivity_x = 1.0;
+        look.smoothing = 0.5;
+
+        let (first, _) = look.update(16.0, &moved_actions(10.0, 0.0));
+        let (second, _) = look.update(16.0, &moved_actions(0.0, 0.0));
+        assert!((first - 5.0).abs() < 1e-5);
+        assert!((second - 2.5).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,117 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use super::event::InputEvent;
+
+/// Writes each frame's time step and input events, one per line prefixed
+/// with the milliseconds since recording started:
+///
+/// ```text
+/// 16.667 frame 16.667
+/// 16.667 key W down
+/// 33.334 frame 16.667
+/// ```
+pub struct Recorder<W: Write> {
+    out: W,
+    time_millis: f32,
+}
+
+impl Recorder<BufWriter<File>> {
+    pub fn create(path: &str) -> io::Result<Recorder<BufWriter<File>>> {
+        Ok(Recorder::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> Recorder<W> {
+    pub fn new(out: W) -> Recorder<W> {
+        Recorder { out, time_millis: 0.0 }
+    }
+
+    pub fn record_frame(&mut self, delta_millis: f32, events: &[InputEvent]) -> io::Result<()> {
+        self.time_millis += delta_millis;
+        writeln!(self.out, "{:.3} frame {}", self.time_millis, delta_millis)?;
+        for event in events {
+            writeln!(self.out, "{:.3} {}", self.time_millis, event)?;
+        }
+        // Flush every frame so a crash still leaves a usable recording.
+        self.out.flush()
+    }
+}
+
+pub struct Frame {
+    pub delta_millis: f32,
+    pub events: Vec<InputEvent>,
+}
+
+/// Feeds a recording back one frame at a time.
+pub struct Playback {
+    frames: Vec<Frame>,
+    next: usize,
+}
+
+fn invalid(line: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("bad recording line `{}`", line))
+}
+
+impl Playback {
+    pub fn load(path: &str) -> io::Result<Playback> {
+        Playback::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(src: &str) -> io::Result<Playback> {
+        let mut frames: Vec<Frame> = Vec::new();
+
+        for line in src.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            // The timestamp is for people reading the file, frame deltas
+            // are what drive playback.
+            let mut parts = line.splitn(2, ' ');
+            parts.next();
+            let rest = parts.next().ok_or_else(|| invalid(line))?;
+
+            if let Some(delta) = rest.strip_prefix("frame ") {
+                let delta_millis = delta.trim().parse().map_err(|_| invalid(line))?;
+                frames.push(Frame { delta_millis, events: Vec::new() });
+            } else {
+                let event = InputEvent::parse(rest).ok_or_else(|| invalid(line))?;
+                frames.last_mut().ok_or_else(|| invalid(line))?.events.push(event);
+            }
+        }
+
+        Ok(Playback { frames, next: 0 })
+    }
+
+    pub fn next_frame(&mut self) -> Option<&Frame> {
+        let frame = self.frames.get(self.next)?;
+        self.next += 1;
+        Some(frame)
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_record_then_play_back() {
+        let mut out = Vec::new();
+        {
+            let mut recorder = super::Recorder::new(&mut out);
+            let press = super::InputEvent::Key { key: super::super::keys::Key::W, pressed: true };
+            recorder.record_frame(16.0, &[press]).unwrap();
+            recorder.record_frame(17.0, &[]).unwrap();
+        }
+
+        let mut playback = super::Playback::parse(&String::from_utf8(out).unwrap()).unwrap();
+        let first = playback.next_frame().unwrap();
+        assert_eq!(first.delta_millis, 16.0);
+        assert_eq!(first.events.len(), 1);
+
+        let second = playback.next_frame().unwrap();
+        assert_eq!(second.delta_millis, 17.0);
+        assert!(second.events.is_empty());
+
+        assert!(playback.next_frame().is_none());
+    }
+
+    #[test]
+    fn test_event_before_first_frame_is_an_error() {
+        assert!(super::Playback::parse("0.000 key W down").is_err());
+    }
+}
@@ -0,0 +1,200 @@
+use glm::Vec3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    Linear,
+    SmoothStep,
+}
+
+impl Easing {
+    fn apply(self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::SmoothStep => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathMode {
+    /// Runs past the last waypoint back to the first, forever.
+    Loop,
+    /// Runs to the last waypoint and back again, forever.
+    PingPong,
+    /// Runs towards the last waypoint while open and back to the first while
+    /// closed, stopping at either end.
+    Triggered,
+}
+
+#[derive(Clone, Copy)]
+struct Progress {
+    distance: f32,
+    heading: f32,
+    paused_millis: f32,
+}
+
+/// Scripted motion along a path of waypoints, for things the player rides
+/// or gets pushed by but which nothing pushes back on.
+pub struct Mover {
+    pub easing: Easing,
+    pub pause_millis: f32,
+    waypoints: Vec<Vec3>,
+    speed: f32,
+    mode: PathMode,
+    open: bool,
+    progress: Progress,
+    previous: Progress,
+}
+
+impl Mover {
+    /// `speed` is in units per second along the path.
+    pub fn new(waypoints: Vec<Vec3>, speed: f32, mode: PathMode) -> Mover {
+        assert!(!waypoints.is_empty(), "a mover needs at least one waypoint");
+        let progress = Progress {
+            distance: 0.0,
+            heading: 1.0,
+            paused_millis: 0.0,
+        };
+        Mover {
+            easing: Easing::Linear,
+            pause_millis: 0.0,
+            waypoints,
+            speed,
+            mode,
+            open: false,
+            progress,
+            previous: progress,
+        }
+    }
+
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+    }
+
+    pub fn update(&mut self, delta_millis: f32) -> Vec3 {
+        self.previous = self.progress;
+        let length = self.length();
+        let step = self.speed * delta_millis / 1000.0;
+        let progress = &mut self.progress;
+
+        match self.mode {
+            PathMode::Loop => {
+                if length > 0.0 {
+                    progress.distance = (progress.distance + step) % length;
+                }
+            }
+            PathMode::PingPong => {
+                if progress.paused_millis > 0.0 {
+                    progress.paused_millis = (progress.paused_millis - delta_millis).max(0.0);
+                } else {
+                    progress.distance += step * progress.heading;
+                    if progress.distance >= length || progress.distance <= 0.0 {
+                        progress.distance = progress.distance.max(0.0).min(length);
+                        progress.heading = -progress.heading;
+                        progress.paused_millis = self.pause_millis;
+                    }
+                }
+            }
+            PathMode::Triggered => {
+                let target = if self.open { length } else { 0.0 };
+                if progress.distance < target {
+                    progress.distance = (progress.distance + step).min(target);
+                } else {
+                    progress.distance = (progress.distance - step).max(target);
+                }
+            }
+        }
+
+        self.position()
+    }
+
+    /// Puts the mover back where it was before the last `update`, used when
+    /// something it cannot push is in the way.
+    pub fn undo(&mut self) {
+        self.progress = self.previous;
+    }
+
+    pub fn position(&self) -> Vec3 {
+        let mut remaining = self.progress.distance;
+        for (from, to) in self.segments() {
+            let segment_length = glm::distance(&from, &to);
+            if remaining <= segment_length && segment_length > 0.0 {
+                let t = self.easing.apply(remaining / segment_length);
+                return glm::lerp(&from, &to, t);
+            }
+            remaining -= segment_length;
+        }
+        *self.waypoints.last().unwrap()
+    }
+
+    fn length(&self) -> f32 {
+        self.segments().map(|(from, to)| glm::distance(&from, &to)).sum()
+    }
+
+    fn segments<'a>(&'a self) -> impl Iterator<Item = (Vec3, Vec3)> + 'a {
+        let closing = match self.mode {
+            PathMode::Loop if self.waypoints.len() > 1 => {
+                Some((*self.waypoints.last().unwrap(), self.waypoints[0]))
+            }
+            _ => None,
+        };
+        self.waypoints
+            .windows(2)
+            .map(|pair| (pair[0], pair[1]))
+            .chain(closing)
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_ping_pong_pauses_and_returns() {
+        let mut lift = super::Mover::new(
+            vec![glm::vec3(0.0, 0.0, 0.0), glm::vec3(0.0, 4.0, 0.0)],
+            2.0,
+            super::PathMode::PingPong
+        );
+        lift.pause_millis = 500.0;
+
+        assert_eq!(lift.update(1000.0), glm::vec3(0.0, 2.0, 0.0));
+        assert_eq!(lift.update(1000.0), glm::vec3(0.0, 4.0, 0.0));
+        assert_eq!(lift.update(400.0), glm::vec3(0.0, 4.0, 0.0));
+        assert_eq!(lift.update(100.0), glm::vec3(0.0, 4.0, 0.0));
+        assert_eq!(lift.update(1000.0), glm::vec3(0.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn test_triggered_opens_closes_and_undoes() {
+        let mut door = super::Mover::new(
+            vec![glm::vec3(0.0, 2.0, 0.0), glm::vec3(0.0, -2.0, 0.0)],
+            4.0,
+            super::PathMode::Triggered
+        );
+        door.easing = super::Easing::SmoothStep;
+
+        assert_eq!(door.update(500.0), glm::vec3(0.0, 2.0, 0.0));
+
+        door.set_open(true);
+        assert_eq!(door.update(500.0), glm::vec3(0.0, 0.0, 0.0));
+        assert_eq!(door.update(2000.0), glm::vec3(0.0, -2.0, 0.0));
+
+        door.set_open(false);
+        let closing = door.update(250.0);
+        assert!(closing.y > -2.0 && closing.y < 0.0);
+        door.undo();
+        assert_eq!(door.position(), glm::vec3(0.0, -2.0, 0.0));
+    }
+
+    #[test]
+    fn test_loop_wraps_to_first_waypoint() {
+        let mut platform = super::Mover::new(
+            vec![glm::vec3(0.0, 0.0, 0.0), glm::vec3(4.0, 0.0, 0.0)],
+            4.0,
+            super::PathMode::Loop
+        );
+
+        assert_eq!(platform.update(1000.0), glm::vec3(4.0, 0.0, 0.0));
+        assert_eq!(platform.update(500.0), glm::vec3(2.0, 0.0, 0.0));
+        assert_eq!(platform.update(500.0), glm::vec3(0.0, 0.0, 0.0));
+    }
+}
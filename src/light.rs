@@ -1,23 +1,57 @@
-use nalgebra::Point
+use glm::Vec3;
 
-use super::render::buffer;
-use super::vertex;
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightKind {
+    /// Shines in every direction from `position`, fading out by `range`.
+    Point { position: Vec3, range: f32 },
+    /// Infinitely far away, like the sun.
+    Directional { direction: Vec3 },
+    /// A cone from `position` along `direction`. Full strength inside
+    /// `inner_angle` degrees of the axis, fading to nothing by `outer_angle`.
+    Spot { position: Vec3, direction: Vec3, range: f32, inner_angle: f32, outer_angle: f32 },
+}
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Light {
+    pub kind: LightKind,
+    pub color: Vec3,
+    pub intensity: f32,
+    /// Only some of the point lights get their shadows drawn each frame,
+    /// the ones nearest the camera.
+    pub casts_shadows: bool,
 }
 
 impl Light {
-    pub fn new(faces: &:[f32]) -> Light {
-        let vbo = buffer::ArrayBuffer::new();
-        
-        vbo.bind();
-        vbo.static_draw_data(faces);
-        vbo.unbind();
+    pub fn point(position: Vec3, color: Vec3, range: f32) -> Light {
+        let kind = LightKind::Point { position, range };
+        Light { kind, color, intensity: 1.0, casts_shadows: false }
+    }
+
+    pub fn directional(direction: Vec3, color: Vec3) -> Light {
+        let direction = direction.normalize();
+        let kind = LightKind::Directional { direction };
+        Light { kind, color, intensity: 1.0, casts_shadows: false }
+    }
+
+    pub fn spot(
+        position: Vec3,
+        direction: Vec3,
+        color: Vec3,
+        range: f32,
+        inner_angle: f32,
+        outer_angle: f32
+    ) -> Light {
+        let direction = direction.normalize();
+        let kind = LightKind::Spot { position, direction, range, inner_angle, outer_angle };
+        Light { kind, color, intensity: 1.0, casts_shadows: false }
+    }
 
-        let mut vao: gl::types::GLuint = 0;
-        gl::GenVertexArrays(1, &mut vao);
-        gl::BindVertexArray(vao);
-        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-        vertex::vertex_attrib_pointers();
+    /// Matches the `*_LIGHT` defines in frag.shdr.
+    pub fn kind_id(&self) -> i32 {
+        match self.kind {
+            LightKind::Point { .. } => 0,
+            LightKind::Directional { .. } => 1,
+            LightKind::Spot { .. } => 2,
+        }
     }
 }
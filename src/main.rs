@@ -4,41 +4,106 @@ extern crate nalgebra_glm as glm;
 
 mod buffer;
 mod camera;
+mod camera_modes;
+mod camera_path;
+mod clock;
 mod collide;
 mod controls;
+mod display;
+mod framebuffer;
+mod frustum;
+mod gl_state;
 mod glm_utils;
+mod input;
+mod kinematic;
+mod light;
 mod maps;
+mod material;
+mod mesh;
 mod model;
 mod obj;
+mod physics;
+mod post;
 mod program;
+mod render_queue;
+mod scene;
+mod settings;
+mod shadow;
+mod skybox;
+mod transform;
 mod vertex;
 mod texture;
+mod trigger;
 
+use camera_modes::{CameraContext, CameraController, PathFollower};
+use camera_path::{CameraPath, PathRecorder};
+use clock::{FrameClock, TICK_MILLIS};
+use frustum::{CullStats, Frustum};
+use gl_state::{DrawStats, GlState};
 use glfw::*;
-use program::{ModelProgram, LightProgram, load_shader_file};
+use input::{Actions, Bindings, Playback, Recorder};
+use material::{AlphaMode, Material};
+use physics::{BodyId, PhysicsWorld, RigidBody, Shape};
+use post::{EffectConfig, PostChain};
+use program::{ModelProgram, LightProgram, PointShadowProgram, ShadowProgram, SkyProgram};
+use program::{load_shader_file, load_shader_file_with_defines, CutoutUniforms, Program};
+use render_queue::{DrawItem, Pass, RenderQueue};
+use settings::Settings;
+use shadow::{CubeShadowMap, ShadowMap, MAX_POINT_SHADOWS};
+use skybox::{SkySource, Skybox};
+use std::fs::File;
+use std::io::BufWriter;
+use std::cell::Cell;
+use std::rc::Rc;
 use std::sync::mpsc::Receiver;
-use std::time::SystemTime;
+use trigger::{Occupant, TriggerEventKind, Triggers};
 
-const HEIGHT: u32 = 1080;
-const WIDTH: u32 = 1920;
+const BINDINGS_PATH: &str = "assets/bindings.cfg";
+const SETTINGS_PATH: &str = "assets/settings.cfg";
+const POST_PATH: &str = "assets/post.cfg";
+const RECORDED_PATH: &str = "assets/recorded.path";
+
+const PLAYER_RADIUS: f32 = 0.5;
+const GRENADE_SPEED: f32 = 15.0;
 
 type Events = Receiver<(f64, WindowEvent)>;
 
-fn load_programs() -> (ModelProgram, LightProgram) {
+fn load_programs(
+    settings: &Settings
+) -> (ModelProgram, LightProgram, ShadowProgram, PointShadowProgram) {
     let vert_shader = load_shader_file("src/shaders/vert.shdr", gl::VERTEX_SHADER);
-    let frag_shader = load_shader_file("src/shaders/frag.shdr", gl::FRAGMENT_SHADER);
+    let frag_shader = load_shader_file_with_defines(
+        "src/shaders/frag.shdr",
+        gl::FRAGMENT_SHADER,
+        &[("MAX_LIGHTS", settings.max_lights)]
+    );
     let light_frag_shader = load_shader_file("src/shaders/light_frag.shdr", gl::FRAGMENT_SHADER);
-
-    let model_program = ModelProgram::from_shaders(&vert_shader, &frag_shader);
+    let shadow_vert_shader = load_shader_file("src/shaders/shadow_vert.shdr", gl::VERTEX_SHADER);
+    let shadow_frag_shader = load_shader_file("src/shaders/shadow_frag.shdr", gl::FRAGMENT_SHADER);
+    let point_shadow_vert_shader =
+        load_shader_file("src/shaders/point_shadow_vert.shdr", gl::VERTEX_SHADER);
+    let point_shadow_frag_shader =
+        load_shader_file("src/shaders/point_shadow_frag.shdr", gl::FRAGMENT_SHADER);
+
+    let model_program = ModelProgram::from_shaders(&vert_shader, &frag_shader, settings.max_lights);
     let light_program = LightProgram::from_shaders(&vert_shader, &light_frag_shader);
+    let shadow_program = ShadowProgram::from_shaders(&shadow_vert_shader, &shadow_frag_shader);
+    let point_shadow_program =
+        PointShadowProgram::from_shaders(&point_shadow_vert_shader, &point_shadow_frag_shader);
 
-    (model_program, light_program)
+    (model_program, light_program, shadow_program, point_shadow_program)
 }
 
-fn create_window(glfw: Glfw) -> (Window, Events) {
+fn load_sky(source: &SkySource) -> (Skybox, SkyProgram) {
+    let vert_shader = load_shader_file("src/shaders/sky_vert.shdr", gl::VERTEX_SHADER);
+    let frag_shader = load_shader_file("src/shaders/sky_frag.shdr", gl::FRAGMENT_SHADER);
+    (Skybox::new(source), SkyProgram::from_shaders(&vert_shader, &frag_shader))
+}
+
+fn create_window(glfw: Glfw, settings: &Settings) -> (Window, Events) {
     let (mut window, events) = glfw.create_window(
-        WIDTH,
-        HEIGHT,
+        settings.width,
+        settings.height,
         "hello i am window",
         glfw::WindowMode::Windowed
     ).expect("failed to create window!");
@@ -47,122 +112,591 @@ fn create_window(glfw: Glfw) -> (Window, Events) {
     window.set_framebuffer_size_polling(true);
     window.set_cursor_mode(CursorMode::Disabled);
     window.set_cursor_pos_polling(true);
+    window.set_focus_polling(true);
     window.set_key_polling(true);
+    window.set_mouse_button_polling(true);
     window.make_current();
 
     (window, events)
 }
 
-#[allow(unused_assignments)]
-fn start_timer() -> impl FnMut() -> f32 {
-    let mut delta_millis = 0.0;
-    let mut last_frame = 0.0;
+fn load_settings() -> Settings {
+    Settings::load(SETTINGS_PATH).unwrap_or_else(|err| {
+        println!("could not load {}: {}, using defaults", SETTINGS_PATH, err);
+        Settings::new()
+    })
+}
 
-    let start = SystemTime::now();
+fn mouse_look(settings: &Settings) -> input::MouseLook {
+    let mut mouse_look = input::MouseLook::new();
+    mouse_look.sensitivity_x = settings.mouse_sensitivity_x;
+    mouse_look.sensitivity_y = settings.mouse_sensitivity_y;
+    mouse_look.invert_y = settings.invert_y;
+    mouse_look.smoothing = settings.mouse_smoothing;
+    mouse_look.acceleration = settings.mouse_acceleration;
+    mouse_look
+}
+
+fn load_bindings() -> Bindings {
+    Bindings::load(BINDINGS_PATH).unwrap_or_else(|err| {
+        println!("could not load {}: {}, using defaults", BINDINGS_PATH, err);
+        Bindings::defaults()
+    })
+}
+
+fn load_post_effects() -> Vec<EffectConfig> {
+    post::load_effects(POST_PATH).unwrap_or_else(|err| {
+        println!("could not load {}: {}, using defaults", POST_PATH, err);
+        post::default_effects()
+    })
+}
 
-    move || {
-        let millis_since_start = SystemTime::now()
-            .duration_since(start)
-            .unwrap()
-            .as_millis() as f32;
+/// `--record <path>` writes every frame's input to `path` and `--replay <path>`
+/// plays such a recording back instead of taking live input.
+fn input_args() -> (Option<Recorder<BufWriter<File>>>, Option<Playback>) {
+    let args: Vec<String> = std::env::args().collect();
+    let mut recorder = None;
+    let mut playback = None;
+
+    for pair in args.windows(2) {
+        match pair[0].as_str() {
+            "--record" => {
+                recorder = Some(Recorder::create(&pair[1]).expect("failed to create recording"));
+            }
+            "--replay" => {
+                playback = Some(Playback::load(&pair[1]).expect("failed to load recording"));
+            }
+            _ => { }
+        }
+    }
+
+    (recorder, playback)
+}
+
+/// `--camera-path <path>` flies the camera along a path instead of the
+/// player and reports frame times at the end, for benchmarking.
+fn camera_path_arg() -> Option<CameraPath> {
+    let args: Vec<String> = std::env::args().collect();
+    args.windows(2)
+        .find(|pair| pair[0] == "--camera-path")
+        .map(|pair| CameraPath::load(&pair[1]).expect("failed to load camera path"))
+}
+
+/// Adds `body` to the world along with a cube model sized to match its shape.
+fn spawn_prop(
+    physics: &mut PhysicsWorld,
+    body: RigidBody,
+    cube: &Rc<mesh::Mesh>,
+    texture_id: gl::types::GLuint
+) -> (BodyId, model::Model) {
+    let mut model = model::Model::with_mesh(Rc::clone(cube), body.position, texture_id);
+    // The test cube is 4 units across.
+    model.transform.scale = match body.shape {
+        Shape::Box(half_extents) => half_extents / 2.0,
+        Shape::Sphere(radius) => glm::vec3(radius, radius, radius) / 2.0,
+    };
+    (physics.add(body), model)
+}
 
-        delta_millis = millis_since_start - last_frame;
-        last_frame = millis_since_start;
-        delta_millis
+/// Draws every shadow casting model inside `frustum` with `program`, which
+/// must already be in use. Blended models let the light through.
+fn draw_shadow_casters(
+    state: &mut GlState,
+    program: &Program,
+    cutout: &CutoutUniforms,
+    models: &[(&model::Model, transform::Transform)],
+    frustum: &Frustum
+) {
+    let mut queue = RenderQueue::new();
+    for (model, transform) in models {
+        let blended = model.material.alpha == AlphaMode::Blend;
+        if model.casts_shadows && !blended && frustum.intersects_aabb(&model.world_aabb()) {
+            queue.push(DrawItem {
+                pass: Pass::Shadow,
+                program,
+                mesh: &model.mesh,
+                material: &model.material,
+                receives_shadows: false,
+                transform: *transform,
+            });
+        }
     }
+    queue.draw(state, |state, material, _| cutout.set_material(state, material));
 }
 
 fn main() {
     let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
     glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
     glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
-    let (mut window, events) = create_window(glfw);
+    let mut display = display::Display::new(load_settings());
+    let (mut window, events) = create_window(glfw, &display.settings);
+    display.apply(&mut glfw, &mut window);
+    if display.settings.raw_mouse && !input::set_raw_mouse_motion(&mut window, true) {
+        println!("raw mouse motion needs glfw 3.3 and platform support, using the OS cursor");
+    }
 
     gl::load_with(
         |s| window.get_proc_address(s) as *const std::os::raw::c_void
     );
 
+    let (width, height) = window.get_framebuffer_size();
     unsafe {
-        gl::Viewport(0, 0, WIDTH as i32, HEIGHT as i32);
         gl::Enable(gl::DEPTH_TEST);
     }
+    let mut post = PostChain::new(width, height, display.settings.msaa);
+    post.output.gamma = display.settings.gamma;
+    post.output.brightness = display.settings.brightness;
+    post.set_effects(&load_post_effects());
 
     let cube_texture = texture::prepare_textures("assets/bliss.png");
+    let cube_mesh = model::Model::cube_mesh();
 
     // let thingy = obj::read_lines().unwrap().compute_faces();
     // let thingy_model = model::Model::new(&thingy, glm::vec3(5.0, 1.5, 20.0));
-    let newcube = model::Model::test_cube_model(glm::vec3(5.0, 1.5, 20.0), cube_texture);
+    let mut newcube = model::Model::with_mesh(
+        Rc::clone(&cube_mesh),
+        glm::vec3(5.0, 1.5, 20.0),
+        cube_texture
+    );
+    newcube.transform.rotation = glm::quat_angle_axis(
+        std::f32::consts::FRAC_PI_4,
+        &glm::vec3(0.0, 1.0, 0.0)
+    );
 
-    let mut all_models = maps::read_map("assets/first.map");
-    all_models.push(newcube);
+    let map = maps::read_map("assets/first.map");
+    let sky = map.sky.as_ref().map(load_sky);
+    let mut scene = scene::Scene::new(map.models, map.lights);
+    scene.models.push(newcube);
+
+    let mut triggers = Triggers::new();
+    // Walking into the exit finishes the level, which for now only shows
+    // in the title.
+    let reached_exit = Rc::new(Cell::new(false));
+    for spawn in map.triggers {
+        let id = triggers.add(&spawn.name, spawn.volume);
+        let reached_exit = Rc::clone(&reached_exit);
+        triggers.on_event(id, move |event| {
+            let entered = event.occupant == Occupant::Player && event.kind == TriggerEventKind::Enter;
+            if entered && event.name == "exit" {
+                reached_exit.set(true);
+            }
+        });
+    }
 
-    let (program, _light_program) = load_programs();
+    let mut movers = Vec::new();
+    for spawn in map.movers {
+        let sensor = spawn.sensor.map(|volume| triggers.add("door", volume));
+        movers.push((spawn.model, spawn.mover, sensor));
+    }
+
+    let (program, _light_program, shadow_program, point_shadow_program) =
+        load_programs(&display.settings);
+    let shadow_map = ShadowMap::new(display.settings.shadow_resolution);
+    let point_shadow_count = display.settings.point_shadows.min(MAX_POINT_SHADOWS);
+    let cube_shadow_maps: Vec<_> = (0..point_shadow_count)
+        .map(|_| CubeShadowMap::new(display.settings.point_shadow_resolution))
+        .collect();
 
-    let light_pos = glm::vec3(5.0, 1.5, 15.0);
     // let light_cube = model::Model::test_cube_model(light_pos, cube_texture);
     // let light_scale = glm::scaling(&glm::vec3(0.1, 0.1, 0.1));
 
-    let projection = glm::perspective(
-        WIDTH as f32 / HEIGHT as f32,
-        std::f32::consts::PI / 3.0,
-        0.1,
-        1000.0
-    );
+    let mut projection = display.projection(width, height).expect("window has no size");
 
-    let mut camera = camera::Camera::new();
-    let mut controls = controls::Controls::new(&mut camera);
-    let mut mark_time = start_timer();
+    let mut colliders: Vec<_> = scene.models.iter().map(|m| m.world_aabb()).collect();
+
+    let mut physics = PhysicsWorld::new();
+    let mut props = Vec::new();
+    for &z in &[12.0, 14.0] {
+        let body = RigidBody::new(
+            Shape::Box(glm::vec3(0.5, 0.5, 0.5)),
+            glm::vec3(8.0, 0.5, z),
+            10.0
+        );
+        props.push(spawn_prop(&mut physics, body, &cube_mesh, cube_texture));
+    }
+
+    let mut controls = controls::Controls::new(camera::Camera::new());
+    let mut view_camera = controls.camera;
+    let mut camera_modes: Vec<Box<dyn CameraController>> = vec![
+        Box::new(camera_modes::FirstPerson),
+        Box::new(camera_modes::ThirdPerson::new()),
+        Box::new(camera_modes::Noclip::new()),
+        Box::new(camera_modes::Spectator::new()),
+    ];
+    let mut camera_mode = 0;
+    let mut actions = Actions::new(load_bindings());
+    let mut mouse_look = mouse_look(&display.settings);
+    let mut gamepad = input::GamepadPoller::new(input::StickResponse::new());
+    let (mut recorder, mut playback) = input_args();
+    let mut fly_through = camera_path_arg().map(PathFollower::new);
+    let mut path_recorder: Option<PathRecorder> = None;
+    let mut benchmark_frames = 0;
+    let mut benchmark_millis = 0.0;
+    let mut last_cull_stats = CullStats::new();
+    let mut last_draw_stats = DrawStats::new();
+    let mut last_camera_mode = camera_mode;
+    let mut last_reached_exit = false;
+    let mut last_recording_path = false;
+
+    let mut clock = FrameClock::new();
+    let mut pending_events: Vec<input::InputEvent> = Vec::new();
+    // Where things were before the latest tick, so frames can be drawn
+    // between it and the one before.
+    let mut previous_camera = view_camera;
+    let mut previous_positions: Vec<glm::Vec3> = Vec::new();
 
     while !window.should_close() {
-        let delta_millis = mark_time();
-        unsafe {
-            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
-        }
+        let mut frame_millis = clock.frame_millis();
 
         glfw.poll_events();
 
+        let mut frame_events: Vec<input::InputEvent> = Vec::new();
         for (_, event) in glfw::flush_messages(&events) {
             match event {
-                glfw::WindowEvent::Key(Key::Q, _, _, _) => {
-                    window.set_should_close(true);
+                // Bindings are reloaded from disk so they can be edited
+                // while playing.
+                glfw::WindowEvent::Key(Key::F5, _, Action::Press, _) => {
+                    actions.bindings = load_bindings();
+                }
+                glfw::WindowEvent::Key(Key::F6, _, Action::Press, _) => {
+                    post.set_effects(&load_post_effects());
+                }
+                // Toggles recording a camera path from wherever the camera
+                // is flown.
+                glfw::WindowEvent::Key(Key::F8, _, Action::Press, _) => {
+                    path_recorder = match path_recorder.take() {
+                        Some(path_recorder) => {
+                            let path = path_recorder.finish(&view_camera);
+                            if let Err(err) = path.save(RECORDED_PATH) {
+                                println!("could not save {}: {}", RECORDED_PATH, err);
+                            }
+                            None
+                        }
+                        None => Some(PathRecorder::new()),
+                    };
+                }
+                glfw::WindowEvent::Key(Key::F10, _, Action::Press, _) => {
+                    display.next_monitor(&mut glfw, &mut window);
+                }
+                glfw::WindowEvent::Key(Key::F11, _, Action::Press, _) => {
+                    display.toggle_fullscreen(&mut glfw, &mut window);
+                }
+                glfw::WindowEvent::FramebufferSize(width, height) => {
+                    // Minimising reports a zero size, keep the old projection
+                    // until we come back.
+                    if let Some(resized) = display.projection(width, height) {
+                        projection = resized;
+                        post.resize(width, height);
+                    }
+                }
+                event => {
+                    if let Some(input_event) = input::translate(&event) {
+                        frame_events.push(input_event);
+                    }
+                }
+            }
+        }
+        gamepad.poll(&mut frame_events);
+
+        if let Some(playback) = &mut playback {
+            match playback.next_frame() {
+                Some(frame) => {
+                    frame_millis = frame.delta_millis;
+                    frame_events = frame.events.clone();
                 }
-                glfw::WindowEvent::Key(key, _, action, _) => {
-                    controls.key_move_callback(key, action);
-                },
-                glfw::WindowEvent::CursorPos(x, y) => {
-                    controls.mouse_callback(x as f32, y as f32);
+                None => window.set_should_close(true),
+            }
+        }
+        // Replays go through the same clock so they tick exactly as the
+        // recording did.
+        if let Some(recorder) = &mut recorder {
+            recorder.record_frame(frame_millis, &frame_events).expect("failed to record input");
+        }
+        clock.accumulate(frame_millis);
+        pending_events.extend(frame_events);
+        if fly_through.is_some() {
+            benchmark_frames += 1;
+            benchmark_millis += frame_millis;
+        }
+
+        while clock.tick() {
+            let delta_millis = TICK_MILLIS;
+            previous_camera = view_camera;
+            previous_positions = scene.models
+                .iter()
+                .chain(props.iter().map(|(_, model)| model))
+                .map(|model| model.transform.position)
+                .collect();
+
+            for input_event in pending_events.drain(..) {
+                actions.handle(&input_event);
+            }
+            let look = mouse_look.update(delta_millis, &actions);
+
+            if actions.is_down(input::Action::Quit) {
+                window.set_should_close(true);
+            }
+
+            if actions.just_pressed(input::Action::CameraMode) {
+                camera_mode = (camera_mode + 1) % camera_modes.len();
+                camera_modes[camera_mode].activate(&view_camera);
+            }
+
+            if actions.just_pressed(input::Action::Fire) {
+                let camera = &controls.camera;
+                let mut grenade = RigidBody::new(
+                    Shape::Sphere(0.2),
+                    camera.pos + camera.front,
+                    1.0
+                );
+                grenade.apply_impulse(camera.front * GRENADE_SPEED);
+                grenade.restitution = 0.6;
+                let (id, mut model) = spawn_prop(&mut physics, grenade, &cube_mesh, cube_texture);
+                model.material = material::Material::metal(cube_texture, glm::vec3(0.8, 0.7, 0.5));
+                props.push((id, model));
+            }
+
+            for (index, mover, sensor) in &mut movers {
+                if let Some(sensor) = sensor {
+                    mover.set_open(triggers.is_occupied(*sensor));
+                }
+                let model = &mut scene.models[*index];
+                let before = model.transform.position;
+                model.transform.position = mover.update(delta_millis);
+                colliders[*index] = model.world_aabb();
+
+                let delta = model.transform.position - before;
+                if !controls.push_by(*index, delta, &colliders) {
+                    mover.undo();
+                    model.transform.position = before;
+                    colliders[*index] = model.world_aabb();
+                }
+            }
+
+            let player_before = controls.camera.pos;
+            if fly_through.is_none() && camera_modes[camera_mode].controls_player() {
+                controls.update(delta_millis, &actions, look, &colliders);
+            }
+
+            let player_velocity = (controls.camera.pos - player_before) / (delta_millis / 1000.0);
+            let body_center = controls.camera.pos - glm::vec3(0.0, 1.0, 0.0);
+            physics.shove(body_center, PLAYER_RADIUS, player_velocity);
+            physics.step(&colliders);
+            let mut occupants = vec![(Occupant::Player, controls.bounds())];
+            occupants.extend(physics.bodies().map(|(id, body)| (Occupant::Body(id), body.aabb())));
+            triggers.update(&occupants);
+
+            for (id, model) in &mut props {
+                let body = physics.body(*id);
+                if !body.is_sleeping() {
+                    model.transform.position = body.position;
                 }
-                _ => { }
             }
+
+            let targets: Vec<glm::Vec3> = physics.bodies().map(|(_, body)| body.position).collect();
+            let context = CameraContext {
+                delta_millis,
+                actions: &actions,
+                look,
+                colliders: &colliders,
+                player: &controls,
+                targets: &targets,
+            };
+            match &mut fly_through {
+                Some(fly_through) => fly_through.update(&mut view_camera, &context),
+                None => camera_modes[camera_mode].update(&mut view_camera, &context),
+            }
+            if let Some(path_recorder) = &mut path_recorder {
+                path_recorder.update(delta_millis, &view_camera);
+            }
+            actions.end_frame();
         }
 
-        controls.update(delta_millis, &all_models);
+        if fly_through.as_ref().is_some_and(|fly_through| fly_through.is_finished()) {
+            println!(
+                "{} frames in {:.2}s, {:.2}ms per frame",
+                benchmark_frames,
+                benchmark_millis / 1000.0,
+                benchmark_millis / benchmark_frames as f32
+            );
+            window.set_should_close(true);
+        }
 
-        let view = controls.camera.view();
+        let alpha = clock.alpha();
+        let camera = previous_camera.lerp(&view_camera, alpha);
+        let view = camera.view();
         // light_program.program.set_used();
         // light_program.mvp.set_vp(&view, &projection);
         // {
-        //     let model = light_cube.translation * light_scale;
-        //     light_program.mvp.set_m(&model);
+        //     let model = light_cube.transform.matrix() * light_scale;
+        //     light_program.mvp.set_m(&model, &light_cube.transform.normal_matrix());
 
         //     light_cube.draw();
         // }
 
-        program.program.set_used();
-        program.lights.set_light(&light_pos, &glm::vec3(1.0, 1.0, 1.0));
+        // Anything spawned during the last tick has nowhere to come from.
+        let models: Vec<_> = scene.models
+            .iter()
+            .chain(props.iter().map(|(_, model)| model))
+            .enumerate()
+            .map(|(i, model)| {
+                let mut transform = model.transform;
+                if let Some(previous) = previous_positions.get(i) {
+                    transform.position = glm::lerp(previous, &model.transform.position, alpha);
+                }
+                (model, transform)
+            })
+            .collect();
+
+        // Post-processing binds behind its back, so start afresh.
+        let mut gl_state = GlState::new();
+
+        // The first directional or spot light casting shadows gets the shadow
+        // map, point lights have cube maps of their own.
+        let shadow_light = scene.lights
+            .iter()
+            .enumerate()
+            .filter(|(_, light)| light.casts_shadows)
+            .find_map(|(i, light)| {
+                shadow::light_space_matrix(light, &camera.pos, display.settings.shadow_distance)
+                    .map(|light_space| (i, light_space))
+            });
+
+        if let Some((_, light_space)) = &shadow_light {
+            shadow_map.bind_for_writing();
+            gl_state.use_program(&shadow_program.program);
+            shadow_program.set_light_space(light_space);
+            let light_frustum = Frustum::from_matrix(light_space);
+            draw_shadow_casters(
+                &mut gl_state,
+                &shadow_program.program,
+                &shadow_program.cutout,
+                &models,
+                &light_frustum
+            );
+        }
+
+        let point_shadows = shadow::nearest_point_shadows(
+            &scene.lights,
+            &camera.pos,
+            cube_shadow_maps.len()
+        );
+        if !point_shadows.is_empty() {
+            gl_state.use_program(&point_shadow_program.program);
+        }
+        for (point_shadow, cube_map) in point_shadows.iter().zip(&cube_shadow_maps) {
+            point_shadow_program.set_light(point_shadow);
+            let faces = shadow::cube_face_matrices(&point_shadow.position, point_shadow.range);
+            for (face, light_space) in faces.iter().enumerate() {
+                cube_map.bind_face_for_writing(face);
+                point_shadow_program.set_face(light_space);
+                draw_shadow_casters(
+                    &mut gl_state,
+                    &point_shadow_program.program,
+                    &point_shadow_program.cutout,
+                    &models,
+                    &Frustum::from_matrix(light_space)
+                );
+            }
+        }
+
+        post.begin_scene();
+
+        gl_state.use_program(&program.program);
+        program.lights.set_lights(&scene.ambient, &scene.lights);
+        program.lights.set_view_pos(&camera.pos);
+        match &shadow_light {
+            Some((index, light_space)) => program.shadow.set_shadow(
+                *index,
+                light_space,
+                display.settings.shadow_bias,
+                shadow_map.depth_texture
+            ),
+            None => program.shadow.clear_shadow(),
+        }
+        let point_shadow_maps: Vec<_> = point_shadows
+            .iter()
+            .zip(&cube_shadow_maps)
+            .map(|(point_shadow, cube_map)| (*point_shadow, cube_map.depth_cubemap))
+            .collect();
+        program.shadow.set_point_shadows(&point_shadow_maps);
 
         program.mvp.set_vp(&view, &projection);
 
-        for cube in &all_models {
-            let model = cube.translation;
-            program.mvp.set_m(&model);
-            program.lights.set_object_color(&glm::vec3(1.0, 1.0, 1.0));
-            // program.lights.set_object_color(&glm::vec3(1.0, 0.5, 0.31));
+        let frustum = Frustum::from_matrix(&(projection * view));
+        let mut cull_stats = CullStats::new();
+        let mut opaque = RenderQueue::new();
+        let mut transparent = RenderQueue::new();
+        for (cube, transform) in &models {
+            if !frustum.intersects_aabb(&cube.world_aabb()) {
+                cull_stats.culled += 1;
+                continue;
+            }
+            cull_stats.drawn += 1;
+            let (pass, queue) = match cube.material.alpha {
+                AlphaMode::Blend => (Pass::Transparent, &mut transparent),
+                _ => (Pass::Opaque, &mut opaque),
+            };
+            queue.push(DrawItem {
+                pass,
+                program: &program.program,
+                mesh: &cube.mesh,
+                material: &cube.material,
+                receives_shadows: cube.receives_shadows,
+                transform: *transform,
+            });
+        }
+        let set_material = |state: &mut GlState, material: &Material, receives_shadows| {
+            program.material.set_material(state, material);
+            program.shadow.set_receives(receives_shadows);
+        };
+        opaque.draw(&mut gl_state, set_material);
+
+        if let Some((skybox, sky_program)) = &sky {
+            gl_state.use_program(&sky_program.program);
+            sky_program.set_vp(&view, &projection);
+            skybox.draw(&mut gl_state);
+        }
 
-            unsafe {
-                gl::ActiveTexture(gl::TEXTURE0);
+        // Depth is tested but not written, so glass doesn't hide glass
+        // further back that sorting got wrong.
+        unsafe {
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            gl::DepthMask(gl::FALSE);
+        }
+        transparent.draw_back_to_front(&mut gl_state, &camera.pos, set_material);
+        unsafe {
+            gl::DepthMask(gl::TRUE);
+            gl::Disable(gl::BLEND);
+        }
+        post.finish();
+        let title_changed = cull_stats != last_cull_stats
+            || gl_state.stats != last_draw_stats
+            || camera_mode != last_camera_mode
+            || reached_exit.get() != last_reached_exit
+            || path_recorder.is_some() != last_recording_path;
+        if title_changed {
+            let mut title = format!(
+                "hello i am window - {} camera, {} drawn, {} culled, {} draws, {} binds skipped",
+                camera_modes[camera_mode].name(),
+                cull_stats.drawn,
+                cull_stats.culled,
+                gl_state.stats.draw_calls,
+                gl_state.stats.skipped_binds
+            );
+            if reached_exit.get() {
+                title.push_str(", exit reached");
+            }
+            if path_recorder.is_some() {
+                title.push_str(", recording camera path");
             }
-            cube.draw();
+            window.set_title(&title);
+            last_cull_stats = cull_stats;
+            last_draw_stats = gl_state.stats;
+            last_camera_mode = camera_mode;
+            last_reached_exit = reached_exit.get();
+            last_recording_path = path_recorder.is_some();
         }
 
         window.swap_buffers();
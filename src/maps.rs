@@ -1,23 +1,174 @@
+use glm::Vec3;
 use std::fs;
+use std::rc::Rc;
+use super::collide::AABB;
+use super::kinematic::{Easing, Mover, PathMode};
+use super::light::Light;
+use super::material::Material;
+use super::mesh::Mesh;
 use super::model::Model;
+use super::skybox::SkySource;
+use super::texture;
 
-pub fn read_map(path: &str) -> Vec<Model> {
+// Doors sink far enough into the floor to be stepped over.
+const DOOR_DROP: f32 = 3.9;
+const DOOR_SPEED: f32 = 4.0;
+const DOOR_SENSOR_REACH: f32 = 6.0;
+const LIFT_HEIGHT: f32 = 4.0;
+const LIFT_SPEED: f32 = 2.0;
+const LIFT_PAUSE_MILLIS: f32 = 1500.0;
+const PLATFORM_LOOP_SIZE: f32 = 8.0;
+const PLATFORM_SPEED: f32 = 2.0;
+// Lamps hang just under the top of the walls.
+const LAMP_HEIGHT: f32 = 3.5;
+const LAMP_RANGE: f32 = 14.0;
+const SPOT_RANGE: f32 = 10.0;
+
+pub struct TriggerSpawn {
+    pub name: String,
+    pub volume: AABB,
+}
+
+/// A mover driving `models[model]`, opened while anything is inside
+/// `sensor` if it has one.
+pub struct MoverSpawn {
+    pub model: usize,
+    pub mover: Mover,
+    pub sensor: Option<AABB>,
+}
+
+pub struct Map {
+    pub models: Vec<Model>,
+    pub triggers: Vec<TriggerSpawn>,
+    pub movers: Vec<MoverSpawn>,
+    pub lights: Vec<Light>,
+    /// Set by a `sky = <paths>` line above the tiles.
+    pub sky: Option<SkySource>,
+}
+
+fn trigger_name(tile: char) -> Option<&'static str> {
+    match tile {
+        'e' => Some("exit"),
+        'c' => Some("checkpoint"),
+        'd' => Some("damage"),
+        _ => None,
+    }
+}
+
+fn door(pos: Vec3) -> (Mover, AABB) {
+    let open = pos - glm::vec3(0.0, DOOR_DROP, 0.0);
+    let mut mover = Mover::new(vec![pos, open], DOOR_SPEED, PathMode::Triggered);
+    mover.easing = Easing::SmoothStep;
+
+    let reach = glm::vec3(DOOR_SENSOR_REACH, 2.0, DOOR_SENSOR_REACH);
+    let sensor = AABB {
+        left_top_front: pos + reach,
+        right_bottom_back: pos - reach,
+    };
+    (mover, sensor)
+}
+
+fn lift(floor: Vec3) -> Mover {
+    let top = floor + glm::vec3(0.0, LIFT_HEIGHT, 0.0);
+    let mut mover = Mover::new(vec![floor, top], LIFT_SPEED, PathMode::PingPong);
+    mover.easing = Easing::SmoothStep;
+    mover.pause_millis = LIFT_PAUSE_MILLIS;
+    mover
+}
+
+/// Circles a square starting at `start` and heading towards +x then +z.
+fn platform(start: Vec3) -> Mover {
+    let size = PLATFORM_LOOP_SIZE;
+    let waypoints = vec![
+        start,
+        start + glm::vec3(size, 0.0, 0.0),
+        start + glm::vec3(size, 0.0, size),
+        start + glm::vec3(0.0, 0.0, size),
+    ];
+    Mover::new(waypoints, PLATFORM_SPEED, PathMode::Loop)
+}
+
+fn slab(cube: &Rc<Mesh>, pos: Vec3, texture_location: gl::types::GLuint) -> Model {
+    // A 4x0.2x4 slab resting on the floor.
+    let mut model = Model::with_mesh(Rc::clone(cube), pos, texture_location);
+    model.transform.scale = glm::vec3(1.0, 0.05, 1.0);
+    model
+}
+
+pub fn read_map(path: &str) -> Map {
     let texture_location = Model::cube_texture();
+    let cube = Model::cube_mesh();
+    let fence_texture = texture::prepare_textures("assets/fence.png");
+    let window_texture = texture::prepare_textures("assets/window.png");
     let src = fs::read_to_string(path).unwrap();
     let mut models = Vec::new();
+    let mut triggers = Vec::new();
+    let mut movers = Vec::new();
+    // A dim sun so nothing is completely unlit, and the walls have
+    // something to cast shadows from.
+    let mut sun = Light::directional(glm::vec3(-0.3, -1.0, -0.2), glm::vec3(0.35, 0.35, 0.4));
+    sun.casts_shadows = true;
+    let mut lights = vec![sun];
+
+    let mut sky = None;
+    let mut rows = Vec::new();
+    for line in src.split("\n") {
+        match line.split_once('=') {
+            Some((name, value)) if name.trim() == "sky" => {
+                sky = Some(SkySource::parse(value.trim()).expect("sky needs one or six images"));
+            }
+            Some((name, _)) => panic!("unknown map setting `{}`", name.trim()),
+            None => rows.push(line),
+        }
+    }
 
-    for (x, line) in src.split("\n").enumerate() {
+    for (x, line) in rows.iter().enumerate() {
         for (z, char) in line.chars().enumerate() {
+            let pos = glm::vec3(
+                (x * 4) as f32, 2.0, (z * 4) as f32
+                );
             if char == 'x' {
-                let pos = glm::vec3(
-                    (x * 4) as f32, 2.0, (z * 4) as f32
-                    );
-                let model = Model::test_cube_model(pos, texture_location);
+                models.push(Model::with_mesh(Rc::clone(&cube), pos, texture_location));
+            } else if char == 'F' {
+                let mut model = Model::with_mesh(Rc::clone(&cube), pos, fence_texture);
+                model.material = Material::cutout(fence_texture);
+                models.push(model);
+            } else if char == 'G' {
+                let mut model = Model::with_mesh(Rc::clone(&cube), pos, window_texture);
+                model.material = Material::glass(window_texture);
                 models.push(model);
+            } else if char == 'D' {
+                let (mover, sensor) = door(pos);
+                movers.push(MoverSpawn { model: models.len(), mover, sensor: Some(sensor) });
+                models.push(Model::with_mesh(Rc::clone(&cube), pos, texture_location));
+            } else if char == 'L' {
+                let floor = glm::vec3(pos.x, 0.1, pos.z);
+                movers.push(MoverSpawn { model: models.len(), mover: lift(floor), sensor: None });
+                models.push(slab(&cube, floor, texture_location));
+            } else if char == 'P' {
+                let floor = glm::vec3(pos.x, 0.1, pos.z);
+                movers.push(MoverSpawn { model: models.len(), mover: platform(floor), sensor: None });
+                models.push(slab(&cube, floor, texture_location));
+            } else if char == '*' {
+                let lamp = glm::vec3(pos.x, LAMP_HEIGHT, pos.z);
+                let mut light = Light::point(lamp, glm::vec3(1.0, 0.85, 0.6), LAMP_RANGE);
+                light.casts_shadows = true;
+                lights.push(light);
+            } else if char == 'S' {
+                let lamp = glm::vec3(pos.x, LAMP_HEIGHT, pos.z);
+                let down = glm::vec3(0.0, -1.0, 0.0);
+                lights.push(Light::spot(lamp, down, glm::vec3(0.6, 0.8, 1.0), SPOT_RANGE, 20.0, 30.0));
+            } else if let Some(name) = trigger_name(char) {
+                let half = glm::vec3(2.0, 2.0, 2.0);
+                let volume = AABB {
+                    left_top_front: pos + half,
+                    right_bottom_back: pos - half,
+                };
+                triggers.push(TriggerSpawn { name: name.to_string(), volume });
             }
         }
     }
 
     models.push(Model::floor_model(0.0));
-    models
+    Map { models, triggers, movers, lights, sky }
 }
@@ -0,0 +1,108 @@
+use glm::Vec3;
+
+/// What a surface does with its alpha, which is the diffuse map's alpha
+/// times `Material::opacity`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlphaMode {
+    /// Alpha is ignored.
+    Opaque,
+    /// Anything less opaque than the cutoff isn't drawn at all and the rest
+    /// is solid, for fences and foliage.
+    Cutout(f32),
+    /// Mixed with whatever is behind it, for glass and water. Drawn after
+    /// everything else and never casts shadows.
+    Blend,
+}
+
+impl AlphaMode {
+    /// Matches the modes in frag.shdr.
+    pub fn id(&self) -> i32 {
+        match self {
+            AlphaMode::Opaque => 0,
+            AlphaMode::Cutout(_) => 1,
+            AlphaMode::Blend => 2,
+        }
+    }
+
+    /// 0 when nothing is cut out.
+    pub fn cutoff(&self) -> f32 {
+        match self {
+            AlphaMode::Cutout(cutoff) => *cutoff,
+            _ => 0.0,
+        }
+    }
+}
+
+/// How a surface responds to light, bound once per draw. Maps multiply
+/// their matching colour, `tint` multiplies everything but emission.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Material {
+    pub diffuse: Vec3,
+    pub specular: Vec3,
+    /// Blinn-Phong exponent, higher is a smaller, sharper highlight.
+    pub shininess: f32,
+    /// Light given off regardless of any lights.
+    pub emissive: Vec3,
+    pub tint: Vec3,
+    pub diffuse_map: Option<gl::types::GLuint>,
+    pub specular_map: Option<gl::types::GLuint>,
+    pub emissive_map: Option<gl::types::GLuint>,
+    pub opacity: f32,
+    pub alpha: AlphaMode,
+}
+
+impl Material {
+    /// A mostly matte surface, like painted wood or cardboard.
+    pub fn textured(diffuse_map: gl::types::GLuint) -> Material {
+        Material {
+            diffuse: glm::vec3(1.0, 1.0, 1.0),
+            specular: glm::vec3(0.1, 0.1, 0.1),
+            shininess: 16.0,
+            emissive: glm::vec3(0.0, 0.0, 0.0),
+            tint: glm::vec3(1.0, 1.0, 1.0),
+            diffuse_map: Some(diffuse_map),
+            specular_map: None,
+            emissive_map: None,
+            opacity: 1.0,
+            alpha: AlphaMode::Opaque,
+        }
+    }
+
+    /// Strong, tight highlights tinted by the surface colour.
+    pub fn metal(diffuse_map: gl::types::GLuint, color: Vec3) -> Material {
+        Material {
+            diffuse: color * 0.6,
+            specular: color,
+            shininess: 128.0,
+            ..Material::textured(diffuse_map)
+        }
+    }
+
+    /// Darker with broad bright highlights, like wet stone.
+    pub fn wet(diffuse_map: gl::types::GLuint) -> Material {
+        Material {
+            diffuse: glm::vec3(0.7, 0.7, 0.7),
+            specular: glm::vec3(0.6, 0.6, 0.6),
+            shininess: 48.0,
+            ..Material::textured(diffuse_map)
+        }
+    }
+
+    /// See-through wherever the diffuse map's alpha is below half.
+    pub fn cutout(diffuse_map: gl::types::GLuint) -> Material {
+        Material {
+            alpha: AlphaMode::Cutout(0.5),
+            ..Material::textured(diffuse_map)
+        }
+    }
+
+    /// Shiny, and as see-through as the diffuse map's alpha says.
+    pub fn glass(diffuse_map: gl::types::GLuint) -> Material {
+        Material {
+            specular: glm::vec3(0.9, 0.9, 0.9),
+            shininess: 96.0,
+            alpha: AlphaMode::Blend,
+            ..Material::textured(diffuse_map)
+        }
+    }
+}
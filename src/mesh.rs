@@ -0,0 +1,77 @@
+use super::buffer;
+use super::collide::AABB;
+use super::gl_state::GlState;
+use super::transform::Transform;
+use super::vertex;
+
+/// Vertices on the GPU that any number of models can share, along with a
+/// buffer of per-instance transforms so they can all be drawn at once.
+pub struct Mesh {
+    vao: gl::types::GLuint,
+    num_verts: usize,
+    aabb: AABB,
+    _position_vbo: buffer::ArrayBuffer,
+    instance_vbo: buffer::ArrayBuffer,
+}
+
+impl Mesh {
+    /// `faces` is position, normal and texture coordinates per vertex.
+    pub fn new(faces: &[f32]) -> Mesh {
+        let vbo = buffer::ArrayBuffer::new();
+        vbo.bind();
+        vbo.static_draw_data(faces);
+        vbo.unbind();
+
+        let instance_vbo = buffer::ArrayBuffer::new();
+
+        let mut vao: gl::types::GLuint = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+
+            vbo.bind();
+            vertex::vertex_attrib_pointers();
+            instance_vbo.bind();
+            vertex::instance_attrib_pointers();
+            instance_vbo.unbind();
+
+            gl::BindVertexArray(0);
+        }
+
+        Mesh {
+            vao,
+            num_verts: faces.len() / vertex::VERTEX_FLOATS,
+            aabb: AABB::new(faces),
+            _position_vbo: vbo,
+            instance_vbo,
+        }
+    }
+
+    /// Bounds in the mesh's own space.
+    pub fn aabb(&self) -> &AABB {
+        &self.aabb
+    }
+
+    pub fn vao(&self) -> gl::types::GLuint {
+        self.vao
+    }
+
+    /// One copy of the mesh per transform, in a single draw call.
+    pub fn draw_instanced(&self, state: &mut GlState, transforms: &[Transform]) {
+        self.instance_vbo.bind();
+        self.instance_vbo.stream_draw_data(&vertex::instance_floats(transforms));
+        self.instance_vbo.unbind();
+
+        state.bind_vertex_array(self.vao);
+        vertex::draw_arrays_instanced(self.num_verts, transforms.len());
+        state.count_draw(transforms.len());
+    }
+}
+
+impl Drop for Mesh {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
@@ -1,81 +1,59 @@
-use glm::{Mat4x4, Vec3};
+use glm::Vec3;
+use std::rc::Rc;
 use super::collide::AABB;
-use super::glm_utils;
-use super::buffer;
-use super::vertex;
+use super::material::Material;
+use super::mesh::Mesh;
 use super::texture;
 use super::obj;
+use super::transform::Transform;
 
 pub struct Model {
-    pub translation: Mat4x4,
-    aabb: AABB,
-    num_verts: usize,
-    _position_vbo: buffer::ArrayBuffer,
-    vao: u32,
-    texture_id: gl::types::GLuint,
+    pub transform: Transform,
+    /// Shared by every model that looks the same so they can be drawn
+    /// together.
+    pub mesh: Rc<Mesh>,
+    pub material: Material,
+    /// Drawn into shadow maps.
+    pub casts_shadows: bool,
+    /// Darkened where a shadow map says something is in the way.
+    pub receives_shadows: bool,
 }
 
 impl Model {
     pub fn new(faces: &[f32], pos: Vec3, texture_id: gl::types::GLuint) -> Model {
-        let vbo = buffer::ArrayBuffer::new();
-
-        vbo.bind();
-        vbo.static_draw_data(faces);
-        vbo.unbind();
-
-        let mut vao: gl::types::GLuint = 0;
-        unsafe {
-            gl::GenVertexArrays(1, &mut vao);
-            gl::BindVertexArray(vao);
-
-            vbo.bind();
-            vertex::vertex_attrib_pointers();
-            vbo.unbind();
-
-            gl::BindVertexArray(0);
-        }
-        let aabb = AABB::new(&faces);
+        Model::with_mesh(Rc::new(Mesh::new(faces)), pos, texture_id)
+    }
 
+    pub fn with_mesh(mesh: Rc<Mesh>, pos: Vec3, texture_id: gl::types::GLuint) -> Model {
         Model { 
-            translation: glm::translation(&pos),
-            aabb,
-            num_verts: (faces.len() / 3),
-            _position_vbo: vbo,
-            vao,
-            texture_id
+            transform: Transform::at(pos),
+            mesh,
+            material: Material::textured(texture_id),
+            casts_shadows: true,
+            receives_shadows: true,
         }
     }
 
-    pub fn collides_with(&self, pos: Vec3) -> bool {
-        let h1 = glm_utils::translate_pos(&self.translation, &self.aabb.left_top_front);
-        let h2 = glm_utils::translate_pos(&self.translation, &self.aabb.right_bottom_back);
-        let aabb = AABB {
-            left_top_front: h1,
-            right_bottom_back: h2,
-        };
-        aabb.is_in_aabb(pos)
-    }
-
-    pub fn draw(&self) {
-        unsafe {
-            gl::BindVertexArray(self.vao);
-            gl::BindTexture(gl::TEXTURE_2D, self.texture_id);
-        }
-
-        vertex::draw_arrays(self.num_verts);
+    pub fn world_aabb(&self) -> AABB {
+        self.mesh.aabb().transformed(&self.transform.matrix())
     }
 
     pub fn floor_model(h: f32) -> Model {
         let floor_model_data = obj::read_lines("assets/floor.obj").unwrap().compute_faces();
         let gravel_texture = texture::prepare_textures("assets/gravel.jpg");
-        Model::new(&floor_model_data, glm::vec3(0.0, h, 0.0), gravel_texture)
+        let mut floor = Model::new(&floor_model_data, glm::vec3(0.0, h, 0.0), gravel_texture);
+        floor.material = Material::wet(gravel_texture);
+        // Nothing is below the floor to shade.
+        floor.casts_shadows = false;
+        floor
     }
 
     pub fn cube_texture() -> gl::types::GLuint {
         texture::prepare_textures("assets/container.jpg")
     }
 
-    pub fn test_cube_model(pos: Vec3, texture_location: gl::types::GLuint) -> Model {
+    /// A 4x4x4 cube centred on the origin.
+    pub fn cube_mesh() -> Rc<Mesh> {
         let cube_verts: Vec<f32> = vec![
             -2.0, -2.0, -2.0,  0.0,  0.0, -1.0, 0.0, 0.0,
             2.0, -2.0, -2.0,  0.0,  0.0, -1.0, 1.0, 0.0,
@@ -119,7 +97,7 @@ impl Model {
             -2.0,  2.0,  2.0,  0.0,  1.0,  0.0, 0.0, 0.0,
             -2.0,  2.0, -2.0,  0.0,  1.0,  0.0, 0.0, 1.0
         ];
-        Model::new(&cube_verts, pos, texture_location)
+        Rc::new(Mesh::new(&cube_verts))
     }
 }
 
@@ -0,0 +1,410 @@
+use glm::Vec3;
+use super::clock::TICK_MILLIS;
+use super::collide::AABB;
+
+// Each game tick is split in two so fast bodies don't tunnel through thin
+// walls.
+const SUBSTEPS: u32 = 2;
+const SUBSTEP_MILLIS: f32 = TICK_MILLIS / SUBSTEPS as f32;
+const GRAVITY: f32 = 25.0;
+
+// Fraction of sliding speed lost per second while in contact with the world.
+const FRICTION: f32 = 4.0;
+const REST_SPEED: f32 = 0.5;
+const SLEEP_SPEED: f32 = 0.05;
+const SLEEP_MILLIS: f32 = 500.0;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Shape {
+    Box(Vec3),
+    Sphere(f32),
+}
+
+pub struct RigidBody {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub shape: Shape,
+    pub restitution: f32,
+    inverse_mass: f32,
+    sleeping: bool,
+    still_millis: f32,
+}
+
+impl RigidBody {
+    /// A `mass` of zero or less makes the body immovable: other bodies
+    /// bounce off it but nothing, not even gravity, moves it.
+    pub fn new(shape: Shape, position: Vec3, mass: f32) -> RigidBody {
+        RigidBody {
+            position,
+            velocity: glm::vec3(0.0, 0.0, 0.0),
+            shape,
+            restitution: 0.0,
+            inverse_mass: if mass > 0.0 { 1.0 / mass } else { 0.0 },
+            sleeping: false,
+            still_millis: 0.0,
+        }
+    }
+
+    pub fn is_immovable(&self) -> bool {
+        self.inverse_mass == 0.0
+    }
+
+    pub fn is_sleeping(&self) -> bool {
+        self.sleeping
+    }
+
+    pub fn wake(&mut self) {
+        self.sleeping = false;
+        self.still_millis = 0.0;
+    }
+
+    pub fn apply_impulse(&mut self, impulse: Vec3) {
+        self.velocity += impulse * self.inverse_mass;
+        self.wake();
+    }
+
+    pub fn aabb(&self) -> AABB {
+        let half = match self.shape {
+            Shape::Box(half_extents) => half_extents,
+            Shape::Sphere(radius) => glm::vec3(radius, radius, radius),
+        };
+        AABB {
+            left_top_front: self.position + half,
+            right_bottom_back: self.position - half,
+        }
+    }
+
+    fn integrate(&mut self, delta_secs: f32) {
+        self.velocity.y -= GRAVITY * delta_secs;
+        self.position += self.velocity * delta_secs;
+    }
+
+    fn update_sleep(&mut self, delta_millis: f32) {
+        if self.velocity.norm() < SLEEP_SPEED {
+            self.still_millis += delta_millis;
+        } else {
+            self.still_millis = 0.0;
+        }
+
+        if self.still_millis >= SLEEP_MILLIS {
+            self.sleeping = true;
+            self.velocity = glm::vec3(0.0, 0.0, 0.0);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BodyId(usize);
+
+/// `normal` points from the other object towards this one, so moving along
+/// it by `depth` separates them.
+struct Contact {
+    normal: Vec3,
+    depth: f32,
+}
+
+pub struct PhysicsWorld {
+    bodies: Vec<RigidBody>,
+}
+
+impl PhysicsWorld {
+    pub fn new() -> PhysicsWorld {
+        PhysicsWorld { bodies: Vec::new() }
+    }
+
+    pub fn add(&mut self, body: RigidBody) -> BodyId {
+        self.bodies.push(body);
+        BodyId(self.bodies.len() - 1)
+    }
+
+    pub fn body(&self, id: BodyId) -> &RigidBody {
+        &self.bodies[id.0]
+    }
+
+    pub fn bodies(&self) -> impl Iterator<Item = (BodyId, &RigidBody)> {
+        self.bodies.iter().enumerate().map(|(i, body)| (BodyId(i), body))
+    }
+
+    /// Advances the simulation by one game tick, `TICK_MILLIS`.
+    pub fn step(&mut self, statics: &[AABB]) {
+        for _ in 0..SUBSTEPS {
+            self.substep(statics);
+        }
+    }
+
+    /// Moves every body overlapping the sphere out of it and makes sure it
+    /// is travelling at least at `velocity` away, e.g. the player walking
+    /// into a crate.
+    pub fn shove(&mut self, center: Vec3, radius: f32, velocity: Vec3) {
+        for body in self.bodies.iter_mut().filter(|b| !b.is_immovable()) {
+            let contact = match contact_sphere_aabb(center, radius, &body.aabb()) {
+                Some(contact) => contact,
+                None => continue,
+            };
+            let away = -contact.normal;
+            body.position += away * contact.depth;
+
+            let pushing = velocity.dot(&away);
+            let moving = body.velocity.dot(&away);
+            if pushing > moving {
+                body.velocity += away * (pushing - moving);
+            }
+            body.wake();
+        }
+    }
+
+    fn substep(&mut self, statics: &[AABB]) {
+        let delta_secs = SUBSTEP_MILLIS / 1000.0;
+
+        for body in self.bodies.iter_mut().filter(|b| !b.sleeping && !b.is_immovable()) {
+            body.integrate(delta_secs);
+            for aabb in statics {
+                if let Some(contact) = contact_body_aabb(body, aabb) {
+                    resolve_static(body, &contact, delta_secs);
+                }
+            }
+        }
+
+        for i in 0..self.bodies.len() {
+            let (head, tail) = self.bodies.split_at_mut(i + 1);
+            let a = &mut head[i];
+            for b in tail.iter_mut() {
+                if a.sleeping && b.sleeping {
+                    continue;
+                }
+                if let Some(contact) = contact_bodies(a, b) {
+                    resolve_bodies(a, b, &contact);
+                }
+            }
+        }
+
+        for body in self.bodies.iter_mut().filter(|b| !b.sleeping) {
+            body.update_sleep(SUBSTEP_MILLIS);
+        }
+    }
+}
+
+fn resolve_static(body: &mut RigidBody, contact: &Contact, delta_secs: f32) {
+    body.position += contact.normal * contact.depth;
+
+    let normal_speed = body.velocity.dot(&contact.normal);
+    if normal_speed < 0.0 {
+        let restitution = if -normal_speed < REST_SPEED { 0.0 } else { body.restitution };
+        body.velocity -= contact.normal * normal_speed * (1.0 + restitution);
+
+        let tangent = body.velocity - contact.normal * body.velocity.dot(&contact.normal);
+        body.velocity -= tangent * (FRICTION * delta_secs).min(1.0);
+    }
+}
+
+fn resolve_bodies(a: &mut RigidBody, b: &mut RigidBody, contact: &Contact) {
+    let total_inverse_mass = a.inverse_mass + b.inverse_mass;
+    if total_inverse_mass <= 0.0 {
+        return;
+    }
+    a.wake();
+    b.wake();
+
+    let correction = contact.normal * (contact.depth / total_inverse_mass);
+    a.position += correction * a.inverse_mass;
+    b.position -= correction * b.inverse_mass;
+
+    let normal_speed = (a.velocity - b.velocity).dot(&contact.normal);
+    if normal_speed < 0.0 {
+        let restitution = a.restitution.max(b.restitution);
+        let impulse = -(1.0 + restitution) * normal_speed / total_inverse_mass;
+        a.velocity += contact.normal * impulse * a.inverse_mass;
+        b.velocity -= contact.normal * impulse * b.inverse_mass;
+    }
+}
+
+fn contact_body_aabb(body: &RigidBody, aabb: &AABB) -> Option<Contact> {
+    match body.shape {
+        Shape::Sphere(radius) => contact_sphere_aabb(body.position, radius, aabb),
+        Shape::Box(_) => contact_aabb_aabb(&body.aabb(), aabb),
+    }
+}
+
+fn contact_bodies(a: &RigidBody, b: &RigidBody) -> Option<Contact> {
+    match (a.shape, b.shape) {
+        (Shape::Sphere(ra), Shape::Sphere(rb)) => {
+            let between = a.position - b.position;
+            let distance = between.norm();
+            if distance >= ra + rb {
+                return None;
+            }
+            let normal = if distance > 0.0 {
+                between / distance
+            } else {
+                glm::vec3(0.0, 1.0, 0.0)
+            };
+            Some(Contact { normal, depth: ra + rb - distance })
+        }
+        (Shape::Sphere(radius), Shape::Box(_)) => {
+            contact_sphere_aabb(a.position, radius, &b.aabb())
+        }
+        (Shape::Box(_), Shape::Sphere(radius)) => {
+            contact_sphere_aabb(b.position, radius, &a.aabb())
+                .map(|c| Contact { normal: -c.normal, depth: c.depth })
+        }
+        (Shape::Box(_), Shape::Box(_)) => contact_aabb_aabb(&a.aabb(), &b.aabb()),
+    }
+}
+
+fn contact_aabb_aabb(a: &AABB, b: &AABB) -> Option<Contact> {
+    // How far `a` has to move along each axis, in the positive and negative
+    // direction, to clear `b`. Works for zero thickness boxes like the floor.
+    let positive = b.left_top_front - a.right_bottom_back;
+    let negative = a.left_top_front - b.right_bottom_back;
+
+    let mut best: Option<Contact> = None;
+    for axis in 0..3 {
+        if positive[axis] <= 0.0 || negative[axis] <= 0.0 {
+            return None;
+        }
+
+        let mut normal = glm::vec3(0.0, 0.0, 0.0);
+        let depth = if positive[axis] < negative[axis] {
+            normal[axis] = 1.0;
+            positive[axis]
+        } else {
+            normal[axis] = -1.0;
+            negative[axis]
+        };
+
+        // Separate along whichever axis needs the smallest push.
+        match best {
+            Some(ref contact) if contact.depth <= depth => {}
+            _ => best = Some(Contact { normal, depth }),
+        }
+    }
+    best
+}
+
+fn contact_sphere_aabb(center: Vec3, radius: f32, aabb: &AABB) -> Option<Contact> {
+    let closest = glm::clamp_vec(&center, &aabb.right_bottom_back, &aabb.left_top_front);
+    let between = center - closest;
+    let distance = between.norm();
+
+    if distance > 0.0 {
+        if distance >= radius {
+            return None;
+        }
+        return Some(Contact { normal: between / distance, depth: radius - distance });
+    }
+
+    // Center is inside the box, push out through the nearest face.
+    let sphere = AABB {
+        left_top_front: center + glm::vec3(radius, radius, radius),
+        right_bottom_back: center - glm::vec3(radius, radius, radius),
+    };
+    contact_aabb_aabb(&sphere, aabb)
+}
+
+#[cfg(test)]
+mod tests {
+
+    fn floor() -> super::AABB {
+        super::AABB {
+            left_top_front: glm::vec3(100.0, 0.0, 100.0),
+            right_bottom_back: glm::vec3(0.0, 0.0, 0.0),
+        }
+    }
+
+    #[test]
+    fn test_sphere_bounces_then_sleeps_on_floor() {
+        let mut world = super::PhysicsWorld::new();
+        let mut grenade = super::RigidBody::new(
+            super::Shape::Sphere(0.2),
+            glm::vec3(5.0, 3.0, 5.0),
+            1.0
+        );
+        grenade.restitution = 0.5;
+        let id = world.add(grenade);
+        let statics = vec![floor()];
+
+        let mut bounced = false;
+        for _ in 0..600 {
+            world.step(&statics);
+            bounced |= world.body(id).velocity.y > 0.0;
+        }
+
+        let grenade = world.body(id);
+        assert!(bounced);
+        assert!(grenade.is_sleeping());
+        assert!((grenade.position.y - 0.2).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_shove_pushes_crate_away() {
+        let mut world = super::PhysicsWorld::new();
+        let crate_id = world.add(super::RigidBody::new(
+            super::Shape::Box(glm::vec3(0.5, 0.5, 0.5)),
+            glm::vec3(5.0, 0.5, 5.0),
+            10.0
+        ));
+        let statics = vec![floor()];
+
+        world.shove(glm::vec3(4.2, 0.5, 5.0), 0.5, glm::vec3(3.0, 0.0, 0.0));
+        for _ in 0..6 {
+            world.step(&statics);
+        }
+
+        let crate_body = world.body(crate_id);
+        assert!(crate_body.position.x > 5.0);
+        assert!(crate_body.position.y >= 0.45);
+    }
+
+    #[test]
+    fn test_boxes_do_not_overlap_after_collision() {
+        let mut world = super::PhysicsWorld::new();
+        let mut moving = super::RigidBody::new(
+            super::Shape::Box(glm::vec3(0.5, 0.5, 0.5)),
+            glm::vec3(3.0, 0.5, 5.0),
+            1.0
+        );
+        moving.velocity = glm::vec3(5.0, 0.0, 0.0);
+        let moving_id = world.add(moving);
+        let resting_id = world.add(super::RigidBody::new(
+            super::Shape::Box(glm::vec3(0.5, 0.5, 0.5)),
+            glm::vec3(5.0, 0.5, 5.0),
+            1.0
+        ));
+        let statics = vec![floor()];
+
+        for _ in 0..60 {
+            world.step(&statics);
+        }
+
+        let gap = world.body(resting_id).position.x - world.body(moving_id).position.x;
+        assert!(gap >= 0.99);
+        assert!(world.body(resting_id).position.x > 5.0);
+    }
+
+    #[test]
+    fn test_massless_body_is_immovable() {
+        let mut world = super::PhysicsWorld::new();
+        let pillar_id = world.add(super::RigidBody::new(
+            super::Shape::Box(glm::vec3(0.5, 0.5, 0.5)),
+            glm::vec3(5.0, 3.0, 5.0),
+            0.0
+        ));
+        let mut moving = super::RigidBody::new(
+            super::Shape::Box(glm::vec3(0.5, 0.5, 0.5)),
+            glm::vec3(5.0, 3.0, 3.0),
+            1.0
+        );
+        moving.velocity = glm::vec3(0.0, 0.0, 5.0);
+        let moving_id = world.add(moving);
+
+        world.shove(glm::vec3(5.0, 3.0, 4.2), 0.5, glm::vec3(0.0, 0.0, 3.0));
+        for _ in 0..30 {
+            world.step(&[]);
+        }
+
+        assert_eq!(world.body(pillar_id).position, glm::vec3(5.0, 3.0, 5.0));
+        let moving = world.body(moving_id);
+        assert!(moving.position.iter().all(|x| x.is_finite()));
+        assert!(moving.position.z <= 4.01);
+    }
+}
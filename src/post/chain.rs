@@ -0,0 +1,157 @@
+use super::super::framebuffer::{self, Framebuffer};
+use super::config::EffectConfig;
+use super::effects::{self, Output, PostEffect};
+
+// Floating point so lights can add up past 1 until tone mapping.
+const SCENE_FORMAT: gl::types::GLenum = gl::RGBA16F;
+
+/// One triangle covering the whole screen, positions come from
+/// post_vert.shdr.
+pub struct ScreenTriangle {
+    vao: gl::types::GLuint,
+}
+
+impl ScreenTriangle {
+    pub fn new() -> ScreenTriangle {
+        let mut vao: gl::types::GLuint = 0;
+        unsafe {
+            // Core profile won't draw without a vertex array bound, even an
+            // empty one.
+            gl::GenVertexArrays(1, &mut vao);
+        }
+        ScreenTriangle { vao }
+    }
+
+    pub fn draw(&self) {
+        unsafe {
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+        }
+    }
+}
+
+impl Drop for ScreenTriangle {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
+
+/// Where a pass draws to.
+pub enum Target<'a> {
+    Screen { width: i32, height: i32 },
+    Buffer(&'a Framebuffer),
+}
+
+impl<'a> Target<'a> {
+    pub fn bind(&self) {
+        match self {
+            Target::Screen { width, height } => framebuffer::bind_screen(*width, *height),
+            Target::Buffer(buffer) => buffer.bind(),
+        }
+    }
+
+    pub fn size(&self) -> (i32, i32) {
+        match self {
+            Target::Screen { width, height } => (*width, *height),
+            Target::Buffer(buffer) => (buffer.width, buffer.height),
+        }
+    }
+}
+
+pub fn bind_texture(unit: u32, texture: gl::types::GLuint) {
+    unsafe {
+        gl::ActiveTexture(gl::TEXTURE0 + unit);
+        gl::BindTexture(gl::TEXTURE_2D, texture);
+    }
+}
+
+/// The scene is drawn off screen, then each effect reads the previous one's
+/// output and `output` draws the last of them to the window.
+pub struct PostChain {
+    effects: Vec<Box<dyn PostEffect>>,
+    pub output: Output,
+    scene: Framebuffer,
+    // Single sampled copy of a multisampled scene for effects to read.
+    resolved: Option<Framebuffer>,
+    ping_pong: Vec<Framebuffer>,
+    triangle: ScreenTriangle,
+}
+
+impl PostChain {
+    /// `samples` of 0 turns multisampling off.
+    pub fn new(width: i32, height: i32, samples: i32) -> PostChain {
+        let (scene, resolved, ping_pong) = buffers(width, height, samples);
+        PostChain {
+            effects: Vec::new(),
+            output: Output::new(),
+            scene,
+            resolved,
+            ping_pong,
+            triangle: ScreenTriangle::new(),
+        }
+    }
+
+    pub fn set_effects(&mut self, configs: &[EffectConfig]) {
+        self.effects = configs.iter().filter_map(effects::build).collect();
+        for effect in self.effects.iter_mut() {
+            effect.resize(self.scene.width, self.scene.height);
+        }
+    }
+
+    pub fn resize(&mut self, width: i32, height: i32) {
+        let (scene, resolved, ping_pong) = buffers(width, height, self.scene.samples);
+        self.scene = scene;
+        self.resolved = resolved;
+        self.ping_pong = ping_pong;
+        for effect in self.effects.iter_mut() {
+            effect.resize(width, height);
+        }
+    }
+
+    /// Everything drawn from here until `finish` goes into the scene.
+    pub fn begin_scene(&self) {
+        self.scene.bind();
+        unsafe {
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        }
+    }
+
+    /// Runs the effects over the scene and leaves the result in the window.
+    pub fn finish(&self) {
+        let mut input = match &self.resolved {
+            Some(resolved) => {
+                self.scene.resolve_into(resolved);
+                resolved.color_texture().unwrap()
+            }
+            None => self.scene.color_texture().unwrap(),
+        };
+
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+        }
+        for (i, effect) in self.effects.iter().enumerate() {
+            // Alternate buffers so no pass reads what it's drawing over.
+            let buffer = &self.ping_pong[i % 2];
+            effect.apply(input, &Target::Buffer(buffer), &self.triangle);
+            input = buffer.color_texture().unwrap();
+        }
+        let screen = Target::Screen { width: self.scene.width, height: self.scene.height };
+        self.output.apply(input, &screen, &self.triangle);
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+}
+
+fn buffers(width: i32, height: i32, samples: i32) -> (Framebuffer, Option<Framebuffer>, Vec<Framebuffer>) {
+    let scene = Framebuffer::new(width, height, SCENE_FORMAT, samples, true);
+    let resolved = if samples > 0 {
+        Some(Framebuffer::new(width, height, SCENE_FORMAT, 0, false))
+    } else {
+        None
+    };
+    let ping_pong = (0..2).map(|_| Framebuffer::new(width, height, SCENE_FORMAT, 0, false)).collect();
+    (scene, resolved, ping_pong)
+}
@@ -0,0 +1,227 @@
+use std::fmt;
+use std::fs;
+use std::io;
+
+const DEFAULT_EFFECTS: &str = include_str!("../../assets/post.cfg");
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToneMapOperator {
+    /// Simple and never clips, but washes out bright colours.
+    Reinhard,
+    /// A fit of the filmic curve from the Academy Color Encoding System.
+    Aces,
+}
+
+impl ToneMapOperator {
+    pub fn from_name(name: &str) -> Option<ToneMapOperator> {
+        match name {
+            "reinhard" => Some(ToneMapOperator::Reinhard),
+            "aces" => Some(ToneMapOperator::Aces),
+            _ => None,
+        }
+    }
+
+    /// Matches the operators in tonemap_frag.shdr.
+    pub fn id(&self) -> i32 {
+        match self {
+            ToneMapOperator::Reinhard => 0,
+            ToneMapOperator::Aces => 1,
+        }
+    }
+}
+
+/// One stage of the post-processing chain and its settings, anything left
+/// out of the file keeps the default from `EffectConfig::from_name`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EffectConfig {
+    /// Smooths jagged edges by blurring along them.
+    Fxaa,
+    /// Bright parts of the image bleed light onto their surroundings.
+    Bloom { threshold: f32, intensity: f32, passes: usize },
+    /// Brings the scene's unbounded brightness down to what the screen can
+    /// show. Effects before it see HDR values, effects after it 0 to 1.
+    ToneMap { operator: ToneMapOperator, exposure: f32 },
+    /// Darkens towards the corners.
+    Vignette { strength: f32, radius: f32 },
+    /// Remaps colours through a 256x16 lookup table image.
+    ColorGrade { lut: String },
+}
+
+#[derive(Debug)]
+pub enum PostConfigError {
+    Io(io::Error),
+    UnknownEffect(String),
+    UnknownSetting(String, String),
+    BadValue(String, String),
+    Malformed(String),
+}
+
+impl fmt::Display for PostConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PostConfigError::Io(err) => write!(f, "{}", err),
+            PostConfigError::UnknownEffect(name) => write!(f, "unknown effect `{}`", name),
+            PostConfigError::UnknownSetting(effect, name) => {
+                write!(f, "unknown setting `{}` for effect `{}`", name, effect)
+            }
+            PostConfigError::BadValue(name, value) => {
+                write!(f, "bad value `{}` for setting `{}`", value, name)
+            }
+            PostConfigError::Malformed(part) => write!(f, "expected `setting value`, got `{}`", part),
+        }
+    }
+}
+
+impl From<io::Error> for PostConfigError {
+    fn from(err: io::Error) -> PostConfigError {
+        PostConfigError::Io(err)
+    }
+}
+
+type Result<T> = std::result::Result<T, PostConfigError>;
+
+impl EffectConfig {
+    pub fn from_name(name: &str) -> Option<EffectConfig> {
+        match name {
+            "fxaa" => Some(EffectConfig::Fxaa),
+            "bloom" => Some(EffectConfig::Bloom { threshold: 1.0, intensity: 0.5, passes: 4 }),
+            "tonemap" => {
+                Some(EffectConfig::ToneMap { operator: ToneMapOperator::Aces, exposure: 1.0 })
+            }
+            "vignette" => Some(EffectConfig::Vignette { strength: 0.35, radius: 0.5 }),
+            "grade" => Some(EffectConfig::ColorGrade { lut: "assets/warm_lut.png".to_string() }),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EffectConfig::Fxaa => "fxaa",
+            EffectConfig::Bloom { .. } => "bloom",
+            EffectConfig::ToneMap { .. } => "tonemap",
+            EffectConfig::Vignette { .. } => "vignette",
+            EffectConfig::ColorGrade { .. } => "grade",
+        }
+    }
+
+    fn set(&mut self, name: &str, value: &str) -> Result<()> {
+        let bad_value = || PostConfigError::BadValue(name.to_string(), value.to_string());
+        let effect = self.name();
+        match (self, name) {
+            (EffectConfig::Bloom { threshold, .. }, "threshold") => {
+                *threshold = value.parse().map_err(|_| bad_value())?
+            }
+            (EffectConfig::Bloom { intensity, .. }, "intensity") => {
+                *intensity = value.parse().map_err(|_| bad_value())?
+            }
+            (EffectConfig::Bloom { passes, .. }, "passes") => {
+                *passes = value.parse().map_err(|_| bad_value())?
+            }
+            (EffectConfig::ToneMap { operator, .. }, "operator") => {
+                *operator = ToneMapOperator::from_name(value).ok_or_else(bad_value)?
+            }
+            (EffectConfig::ToneMap { exposure, .. }, "exposure") => {
+                *exposure = value.parse().map_err(|_| bad_value())?
+            }
+            (EffectConfig::Vignette { strength, .. }, "strength") => {
+                *strength = value.parse().map_err(|_| bad_value())?
+            }
+            (EffectConfig::Vignette { radius, .. }, "radius") => {
+                *radius = value.parse().map_err(|_| bad_value())?
+            }
+            (EffectConfig::ColorGrade { lut }, "lut") => *lut = value.to_string(),
+            _ => {
+                return Err(PostConfigError::UnknownSetting(effect.to_string(), name.to_string()))
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn default_effects() -> Vec<EffectConfig> {
+    parse_effects(DEFAULT_EFFECTS).unwrap()
+}
+
+pub fn load_effects(path: &str) -> Result<Vec<EffectConfig>> {
+    let src = fs::read_to_string(path)?;
+    parse_effects(&src)
+}
+
+/// Reads one effect per line, in the order they're applied, as `effect` or
+/// `effect = setting value, setting value`. Blank lines and anything after
+/// a `#` are skipped.
+pub fn parse_effects(src: &str) -> Result<Vec<EffectConfig>> {
+    let mut effects = Vec::new();
+
+    for line in src.lines() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut sides = line.splitn(2, '=');
+        let name = sides.next().unwrap().trim();
+        let mut effect = EffectConfig::from_name(name)
+            .ok_or_else(|| PostConfigError::UnknownEffect(name.to_string()))?;
+
+        let settings = sides.next().unwrap_or("");
+        for setting in settings.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let mut words = setting.splitn(2, char::is_whitespace);
+            let setting_name = words.next().unwrap();
+            let value = words
+                .next()
+                .ok_or_else(|| PostConfigError::Malformed(setting.to_string()))?
+                .trim();
+            effect.set(setting_name, value)?;
+        }
+        effects.push(effect);
+    }
+
+    Ok(effects)
+}
+
+mod tests {
+
+    #[test]
+    fn test_parse_effects_in_order() {
+        let effects = super::parse_effects("
+            # comment
+            vignette = radius 0.25
+            fxaa
+            bloom = threshold 1.5, passes 2   # brighter only
+            tonemap = operator reinhard
+        ").unwrap();
+
+        assert_eq!(effects, vec![
+            super::EffectConfig::Vignette { strength: 0.35, radius: 0.25 },
+            super::EffectConfig::Fxaa,
+            super::EffectConfig::Bloom { threshold: 1.5, intensity: 0.5, passes: 2 },
+            super::EffectConfig::ToneMap {
+                operator: super::ToneMapOperator::Reinhard,
+                exposure: 1.0,
+            },
+        ]);
+    }
+
+    #[test]
+    fn test_parse_effect_errors() {
+        match super::parse_effects("blur") {
+            Err(super::PostConfigError::UnknownEffect(name)) => assert_eq!(name, "blur"),
+            _ => panic!("expected unknown effect"),
+        }
+        match super::parse_effects("fxaa = threshold 2") {
+            Err(super::PostConfigError::UnknownSetting(effect, name)) => {
+                assert_eq!((effect.as_str(), name.as_str()), ("fxaa", "threshold"));
+            }
+            _ => panic!("expected unknown setting"),
+        }
+        assert!(super::parse_effects("bloom = passes many").is_err());
+        assert!(super::parse_effects("bloom = passes").is_err());
+        assert!(super::parse_effects("tonemap = operator filmic").is_err());
+    }
+
+    #[test]
+    fn test_defaults_parse() {
+        assert!(!super::default_effects().is_empty());
+    }
+}
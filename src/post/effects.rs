@@ -0,0 +1,247 @@
+use std::path::Path;
+use super::super::framebuffer::Framebuffer;
+use super::super::program::{load_shader_file, PostProgram};
+use super::super::texture;
+use super::chain::{bind_texture, ScreenTriangle, Target};
+use super::config::{EffectConfig, ToneMapOperator};
+
+/// One full-screen stage of the post-processing chain.
+pub trait PostEffect {
+    /// Called before the first `apply` and whenever the screen changes size.
+    fn resize(&mut self, _width: i32, _height: i32) { }
+
+    /// Reads `input` and draws over all of `target`.
+    fn apply(&self, input: gl::types::GLuint, target: &Target, triangle: &ScreenTriangle);
+}
+
+/// `None` for effects that can't be set up, such as a missing lookup table,
+/// so one bad line doesn't take the others with it.
+pub fn build(config: &EffectConfig) -> Option<Box<dyn PostEffect>> {
+    match config {
+        EffectConfig::Fxaa => Some(Box::new(Fxaa::new())),
+        EffectConfig::Bloom { threshold, intensity, passes } => {
+            Some(Box::new(Bloom::new(*threshold, *intensity, *passes)))
+        }
+        EffectConfig::ToneMap { operator, exposure } => {
+            Some(Box::new(ToneMap::new(*operator, *exposure)))
+        }
+        EffectConfig::Vignette { strength, radius } => {
+            Some(Box::new(Vignette::new(*strength, *radius)))
+        }
+        EffectConfig::ColorGrade { lut } => {
+            if Path::new(lut).exists() {
+                Some(Box::new(ColorGrade::new(lut)))
+            } else {
+                println!("could not find lookup table {}, skipping grade", lut);
+                None
+            }
+        }
+    }
+}
+
+/// Builds a pass from `frag` with `screen` reading texture unit 0.
+fn post_program(frag: &str, uniforms: &[&str]) -> PostProgram {
+    let vert_shader = load_shader_file("src/shaders/post_vert.shdr", gl::VERTEX_SHADER);
+    let frag_shader = load_shader_file(frag, gl::FRAGMENT_SHADER);
+    let mut names = vec!["screen"];
+    names.extend_from_slice(uniforms);
+    let program = PostProgram::from_shaders(&vert_shader, &frag_shader, &names);
+    program.program.set_used();
+    program.set_1i("screen", 0);
+    program
+}
+
+pub struct Fxaa {
+    program: PostProgram,
+}
+
+impl Fxaa {
+    pub fn new() -> Fxaa {
+        Fxaa { program: post_program("src/shaders/fxaa_frag.shdr", &["texel_size"]) }
+    }
+}
+
+impl PostEffect for Fxaa {
+    fn apply(&self, input: gl::types::GLuint, target: &Target, triangle: &ScreenTriangle) {
+        let (width, height) = target.size();
+        target.bind();
+        self.program.program.set_used();
+        self.program.set_2f("texel_size", 1.0 / width as f32, 1.0 / height as f32);
+        bind_texture(0, input);
+        triangle.draw();
+    }
+}
+
+/// Blurs the bright parts at half resolution, which is cheaper and spreads
+/// further, then adds them back over the image.
+pub struct Bloom {
+    threshold: f32,
+    intensity: f32,
+    passes: usize,
+    extract: PostProgram,
+    blur: PostProgram,
+    combine: PostProgram,
+    buffers: Vec<Framebuffer>,
+}
+
+impl Bloom {
+    pub fn new(threshold: f32, intensity: f32, passes: usize) -> Bloom {
+        let combine = post_program("src/shaders/bloom_combine_frag.shdr", &["bloom", "intensity"]);
+        combine.set_1i("bloom", 1);
+        Bloom {
+            threshold,
+            intensity,
+            passes,
+            extract: post_program("src/shaders/bloom_extract_frag.shdr", &["threshold"]),
+            blur: post_program("src/shaders/blur_frag.shdr", &["direction"]),
+            combine,
+            buffers: Vec::new(),
+        }
+    }
+}
+
+impl PostEffect for Bloom {
+    fn resize(&mut self, width: i32, height: i32) {
+        let (width, height) = ((width / 2).max(1), (height / 2).max(1));
+        self.buffers = (0..2)
+            .map(|_| Framebuffer::new(width, height, gl::RGBA16F, 0, false))
+            .collect();
+    }
+
+    fn apply(&self, input: gl::types::GLuint, target: &Target, triangle: &ScreenTriangle) {
+        let (first, second) = (&self.buffers[0], &self.buffers[1]);
+
+        first.bind();
+        self.extract.program.set_used();
+        self.extract.set_1f("threshold", self.threshold);
+        bind_texture(0, input);
+        triangle.draw();
+
+        // Horizontal then vertical, ending up back in the first buffer.
+        self.blur.program.set_used();
+        let texel = (1.0 / first.width as f32, 1.0 / first.height as f32);
+        for _ in 0..self.passes {
+            second.bind();
+            self.blur.set_2f("direction", texel.0, 0.0);
+            bind_texture(0, first.color_texture().unwrap());
+            triangle.draw();
+
+            first.bind();
+            self.blur.set_2f("direction", 0.0, texel.1);
+            bind_texture(0, second.color_texture().unwrap());
+            triangle.draw();
+        }
+
+        target.bind();
+        self.combine.program.set_used();
+        self.combine.set_1f("intensity", self.intensity);
+        bind_texture(0, input);
+        bind_texture(1, first.color_texture().unwrap());
+        triangle.draw();
+    }
+}
+
+pub struct ToneMap {
+    operator: ToneMapOperator,
+    exposure: f32,
+    program: PostProgram,
+}
+
+impl ToneMap {
+    pub fn new(operator: ToneMapOperator, exposure: f32) -> ToneMap {
+        let program = post_program("src/shaders/tonemap_frag.shdr", &["operator", "exposure"]);
+        ToneMap { operator, exposure, program }
+    }
+}
+
+impl PostEffect for ToneMap {
+    fn apply(&self, input: gl::types::GLuint, target: &Target, triangle: &ScreenTriangle) {
+        target.bind();
+        self.program.program.set_used();
+        self.program.set_1i("operator", self.operator.id());
+        self.program.set_1f("exposure", self.exposure);
+        bind_texture(0, input);
+        triangle.draw();
+    }
+}
+
+pub struct Vignette {
+    strength: f32,
+    radius: f32,
+    program: PostProgram,
+}
+
+impl Vignette {
+    pub fn new(strength: f32, radius: f32) -> Vignette {
+        let program = post_program("src/shaders/vignette_frag.shdr", &["strength", "radius"]);
+        Vignette { strength, radius, program }
+    }
+}
+
+impl PostEffect for Vignette {
+    fn apply(&self, input: gl::types::GLuint, target: &Target, triangle: &ScreenTriangle) {
+        target.bind();
+        self.program.program.set_used();
+        self.program.set_1f("strength", self.strength);
+        self.program.set_1f("radius", self.radius);
+        bind_texture(0, input);
+        triangle.draw();
+    }
+}
+
+pub struct ColorGrade {
+    lut: gl::types::GLuint,
+    program: PostProgram,
+}
+
+impl ColorGrade {
+    pub fn new(lut_path: &str) -> ColorGrade {
+        let program = post_program("src/shaders/grade_frag.shdr", &["lut"]);
+        program.set_1i("lut", 1);
+        ColorGrade { lut: texture::prepare_lut(lut_path), program }
+    }
+}
+
+impl PostEffect for ColorGrade {
+    fn apply(&self, input: gl::types::GLuint, target: &Target, triangle: &ScreenTriangle) {
+        target.bind();
+        self.program.program.set_used();
+        bind_texture(0, input);
+        bind_texture(1, self.lut);
+        triangle.draw();
+    }
+}
+
+impl Drop for ColorGrade {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.lut);
+        }
+    }
+}
+
+/// Always the last pass, gamma encodes for the screen.
+pub struct Output {
+    pub gamma: f32,
+    /// Scales everything before gamma, 1 leaves it alone.
+    pub brightness: f32,
+    program: PostProgram,
+}
+
+impl Output {
+    pub fn new() -> Output {
+        let program = post_program("src/shaders/output_frag.shdr", &["gamma", "brightness"]);
+        Output { gamma: 2.2, brightness: 1.0, program }
+    }
+}
+
+impl PostEffect for Output {
+    fn apply(&self, input: gl::types::GLuint, target: &Target, triangle: &ScreenTriangle) {
+        target.bind();
+        self.program.program.set_used();
+        self.program.set_1f("gamma", self.gamma);
+        self.program.set_1f("brightness", self.brightness);
+        bind_texture(0, input);
+        triangle.draw();
+    }
+}
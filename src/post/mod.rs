@@ -0,0 +1,6 @@
+mod chain;
+mod config;
+mod effects;
+
+pub use self::chain::*;
+pub use self::config::*;
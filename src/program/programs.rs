@@ -1,10 +1,14 @@
 use glm::{Mat4x4, Vec3};
+use super::super::gl_state::GlState;
+use super::super::light::{Light, LightKind};
+use super::super::material::{AlphaMode, Material};
+use super::super::shadow::{PointShadow, MAX_POINT_SHADOWS};
 use super::shader_program::*;
 use super::uniform::{get_uniform_location, Uniform};
 
+/// Model matrices come from the mesh's instance buffer instead.
 pub struct MVPUniforms {
     view_loc: Uniform,
-    model_loc: Uniform,
     projection_loc: Uniform,
 }
 
@@ -12,41 +16,392 @@ impl MVPUniforms {
     pub fn for_program(program: &Program) -> MVPUniforms {
         let projection_loc = get_uniform_location(program.id, "projection").unwrap();
         let view_loc = get_uniform_location(program.id, "view").unwrap();
-        let model_loc = get_uniform_location(program.id, "model").unwrap();
-        MVPUniforms { model_loc, view_loc, projection_loc, }
+        MVPUniforms { view_loc, projection_loc }
     }
 
     pub fn set_vp(&self, view: &Mat4x4, projection: &Mat4x4) {
         self.view_loc.set_uniform_matrix4fv(view);
         self.projection_loc.set_uniform_matrix4fv(projection);
     }
+}
+
+struct LightSlot {
+    kind_loc: Uniform,
+    position_loc: Option<Uniform>,
+    direction_loc: Option<Uniform>,
+    color_loc: Uniform,
+    range_loc: Option<Uniform>,
+    inner_cos_loc: Option<Uniform>,
+    outer_cos_loc: Option<Uniform>,
+}
 
-    pub fn set_m(&self, model: &Mat4x4) {
-        self.model_loc.set_uniform_matrix4fv(model);
+impl LightSlot {
+    fn for_program(program: &Program, index: usize) -> LightSlot {
+        // Members no kind of light reads can be optimised out.
+        let loc = |member: &str| {
+            get_uniform_location(program.id, &format!("lights[{}].{}", index, member))
+        };
+        LightSlot {
+            kind_loc: loc("kind").unwrap(),
+            position_loc: loc("position").ok(),
+            direction_loc: loc("direction").ok(),
+            color_loc: loc("color").unwrap(),
+            range_loc: loc("range").ok(),
+            inner_cos_loc: loc("inner_cos").ok(),
+            outer_cos_loc: loc("outer_cos").ok(),
+        }
+    }
+
+    fn set(&self, light: &Light) {
+        self.kind_loc.set_uniform_1i(light.kind_id());
+        self.color_loc.set_uniform_vec3(&(light.color * light.intensity));
+
+        let (position, direction, range, inner_angle, outer_angle) = match light.kind {
+            LightKind::Point { position, range } => (Some(position), None, range, 0.0, 0.0),
+            LightKind::Directional { direction } => (None, Some(direction), 0.0, 0.0, 0.0),
+            LightKind::Spot { position, direction, range, inner_angle, outer_angle } => {
+                (Some(position), Some(direction), range, inner_angle, outer_angle)
+            }
+        };
+        if let (Some(loc), Some(position)) = (&self.position_loc, position) {
+            loc.set_uniform_vec3(&position);
+        }
+        if let (Some(loc), Some(direction)) = (&self.direction_loc, direction) {
+            loc.set_uniform_vec3(&direction);
+        }
+        if let Some(loc) = &self.range_loc {
+            loc.set_uniform_1f(range);
+        }
+        if let Some(loc) = &self.inner_cos_loc {
+            loc.set_uniform_1f(inner_angle.to_radians().cos());
+        }
+        if let Some(loc) = &self.outer_cos_loc {
+            loc.set_uniform_1f(outer_angle.to_radians().cos());
+        }
     }
 }
 
 pub struct LightUniforms {
-    color_loc: Uniform,
-    pos_loc: Uniform,
-    object_color_loc: Uniform,
+    ambient_loc: Uniform,
+    count_loc: Uniform,
+    slots: Vec<LightSlot>,
+    view_pos_loc: Uniform,
 }
 
 impl LightUniforms {
-    pub fn for_program(program: &Program) -> LightUniforms {
-        let color_loc = get_uniform_location(program.id, "light_color").unwrap();
-        let object_color_loc = get_uniform_location(program.id, "object_color").unwrap();
-        let pos_loc = get_uniform_location(program.id, "light_pos").unwrap();
-        LightUniforms { color_loc, object_color_loc, pos_loc, }
+    /// `max_lights` has to match the size of the shader's `lights` array.
+    pub fn for_program(program: &Program, max_lights: usize) -> LightUniforms {
+        let ambient_loc = get_uniform_location(program.id, "ambient").unwrap();
+        let count_loc = get_uniform_location(program.id, "light_count").unwrap();
+        let view_pos_loc = get_uniform_location(program.id, "view_pos").unwrap();
+        let slots = (0..max_lights).map(|i| LightSlot::for_program(program, i)).collect();
+        LightUniforms { ambient_loc, count_loc, slots, view_pos_loc, }
+    }
+
+    /// Anything past the shader's maximum is left out.
+    pub fn set_lights(&self, ambient: &Vec3, lights: &[Light]) {
+        self.ambient_loc.set_uniform_vec3(ambient);
+        let count = lights.len().min(self.slots.len());
+        self.count_loc.set_uniform_1i(count as i32);
+        for (slot, light) in self.slots.iter().zip(lights) {
+            slot.set(light);
+        }
+    }
+
+    /// Where the camera is, for specular highlights.
+    pub fn set_view_pos(&self, pos: &Vec3) {
+        self.view_pos_loc.set_uniform_vec3(pos);
+    }
+}
+
+// Texture units each kind of map is bound to.
+const DIFFUSE_UNIT: u32 = 0;
+const SPECULAR_UNIT: u32 = 1;
+const EMISSIVE_UNIT: u32 = 2;
+const SHADOW_UNIT: u32 = 3;
+// Point shadow maps take this unit and the ones after it.
+const POINT_SHADOW_UNIT: u32 = 4;
+
+pub struct MaterialUniforms {
+    diffuse_loc: Uniform,
+    specular_loc: Uniform,
+    shininess_loc: Uniform,
+    emissive_loc: Uniform,
+    tint_loc: Uniform,
+    has_diffuse_map_loc: Uniform,
+    has_specular_map_loc: Uniform,
+    has_emissive_map_loc: Uniform,
+    opacity_loc: Uniform,
+    alpha_mode_loc: Uniform,
+    alpha_cutoff_loc: Uniform,
+}
+
+impl MaterialUniforms {
+    /// Also points the map samplers at their texture units, so `program`
+    /// must be in use.
+    pub fn for_program(program: &Program) -> MaterialUniforms {
+        let loc = |name: &str| get_uniform_location(program.id, name).unwrap();
+        for &(sampler, unit) in &[
+            ("diffuse_map", DIFFUSE_UNIT),
+            ("specular_map", SPECULAR_UNIT),
+            ("emissive_map", EMISSIVE_UNIT),
+        ] {
+            if let Ok(sampler_loc) = get_uniform_location(program.id, sampler) {
+                sampler_loc.set_uniform_1i(unit as i32);
+            }
+        }
+        MaterialUniforms {
+            diffuse_loc: loc("material.diffuse"),
+            specular_loc: loc("material.specular"),
+            shininess_loc: loc("material.shininess"),
+            emissive_loc: loc("material.emissive"),
+            tint_loc: loc("material.tint"),
+            has_diffuse_map_loc: loc("material.has_diffuse_map"),
+            has_specular_map_loc: loc("material.has_specular_map"),
+            has_emissive_map_loc: loc("material.has_emissive_map"),
+            opacity_loc: loc("material.opacity"),
+            alpha_mode_loc: loc("material.alpha_mode"),
+            alpha_cutoff_loc: loc("material.alpha_cutoff"),
+        }
+    }
+
+    pub fn set_material(&self, state: &mut GlState, material: &Material) {
+        self.diffuse_loc.set_uniform_vec3(&material.diffuse);
+        self.specular_loc.set_uniform_vec3(&material.specular);
+        self.shininess_loc.set_uniform_1f(material.shininess);
+        self.emissive_loc.set_uniform_vec3(&material.emissive);
+        self.tint_loc.set_uniform_vec3(&material.tint);
+        self.opacity_loc.set_uniform_1f(material.opacity);
+        self.alpha_mode_loc.set_uniform_1i(material.alpha.id());
+        self.alpha_cutoff_loc.set_uniform_1f(material.alpha.cutoff());
+
+        for &(map, unit, has_map_loc) in &[
+            (material.diffuse_map, DIFFUSE_UNIT, &self.has_diffuse_map_loc),
+            (material.specular_map, SPECULAR_UNIT, &self.has_specular_map_loc),
+            (material.emissive_map, EMISSIVE_UNIT, &self.has_emissive_map_loc),
+        ] {
+            has_map_loc.set_uniform_1i(map.is_some() as i32);
+            if let Some(texture_id) = map {
+                state.bind_texture(unit, texture_id);
+            }
+        }
+    }
+}
+
+/// Lets the depth only programs skip the see-through parts of cutouts.
+pub struct CutoutUniforms {
+    alpha_cutoff_loc: Uniform,
+}
+
+impl CutoutUniforms {
+    /// Points the diffuse map sampler at its texture unit, so `program` must
+    /// be in use.
+    pub fn for_program(program: &Program) -> CutoutUniforms {
+        let loc = |name: &str| get_uniform_location(program.id, name).unwrap();
+        loc("diffuse_map").set_uniform_1i(DIFFUSE_UNIT as i32);
+        CutoutUniforms { alpha_cutoff_loc: loc("alpha_cutoff") }
+    }
+
+    pub fn set_material(&self, state: &mut GlState, material: &Material) {
+        let cutoff = match (material.alpha, material.diffuse_map) {
+            (AlphaMode::Cutout(cutoff), Some(texture_id)) => {
+                state.bind_texture(DIFFUSE_UNIT, texture_id);
+                cutoff
+            }
+            _ => 0.0,
+        };
+        self.alpha_cutoff_loc.set_uniform_1f(cutoff);
+    }
+}
+
+pub struct ShadowUniforms {
+    light_space_loc: Uniform,
+    shadow_light_loc: Uniform,
+    bias_loc: Uniform,
+    receives_loc: Uniform,
+    point_light_locs: Vec<Uniform>,
+}
+
+impl ShadowUniforms {
+    /// Points the shadow map sampler at its texture unit, so `program` must
+    /// be in use.
+    pub fn for_program(program: &Program) -> ShadowUniforms {
+        let loc = |name: &str| get_uniform_location(program.id, name).unwrap();
+        loc("shadow_map").set_uniform_1i(SHADOW_UNIT as i32);
+        for slot in 0..MAX_POINT_SHADOWS {
+            let unit = POINT_SHADOW_UNIT + slot as u32;
+            loc(&format!("point_shadow_maps[{}]", slot)).set_uniform_1i(unit as i32);
+        }
+        let point_light_locs = (0..MAX_POINT_SHADOWS)
+            .map(|slot| loc(&format!("point_shadow_lights[{}]", slot)))
+            .collect();
+        ShadowUniforms {
+            light_space_loc: loc("light_space"),
+            shadow_light_loc: loc("shadow_light"),
+            bias_loc: loc("shadow_bias"),
+            receives_loc: loc("receives_shadows"),
+            point_light_locs,
+        }
+    }
+
+    /// Pairs each shadow with its cube map's texture. Slots left over are
+    /// marked unused.
+    pub fn set_point_shadows(&self, shadows: &[(PointShadow, gl::types::GLuint)]) {
+        for (slot, loc) in self.point_light_locs.iter().enumerate() {
+            match shadows.get(slot) {
+                Some((shadow, depth_cubemap)) => {
+                    loc.set_uniform_1i(shadow.light as i32);
+                    unsafe {
+                        gl::ActiveTexture(gl::TEXTURE0 + POINT_SHADOW_UNIT + slot as u32);
+                        gl::BindTexture(gl::TEXTURE_CUBE_MAP, *depth_cubemap);
+                    }
+                }
+                None => loc.set_uniform_1i(-1),
+            }
+        }
+    }
+
+    /// `light_index` is the light's position in the list given to
+    /// `LightUniforms::set_lights`.
+    pub fn set_shadow(
+        &self,
+        light_index: usize,
+        light_space: &Mat4x4,
+        bias: f32,
+        depth_texture: gl::types::GLuint
+    ) {
+        self.shadow_light_loc.set_uniform_1i(light_index as i32);
+        self.light_space_loc.set_uniform_matrix4fv(light_space);
+        self.bias_loc.set_uniform_1f(bias);
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + SHADOW_UNIT);
+            gl::BindTexture(gl::TEXTURE_2D, depth_texture);
+        }
+    }
+
+    /// Lights everything as if nothing were in the way of the shadow light.
+    pub fn clear_shadow(&self) {
+        self.shadow_light_loc.set_uniform_1i(-1);
+    }
+
+    pub fn set_receives(&self, receives: bool) {
+        self.receives_loc.set_uniform_1i(receives as i32);
+    }
+}
+
+/// Draws depth only, from a light's point of view.
+pub struct ShadowProgram {
+    pub program: Program,
+    pub cutout: CutoutUniforms,
+    light_space_loc: Uniform,
+}
+
+impl ShadowProgram {
+    pub fn from_shaders(vert: &Shader, frag: &Shader) -> ShadowProgram {
+        let program = Program::from_shaders(vert, frag).unwrap();
+        let light_space_loc = get_uniform_location(program.id, "light_space").unwrap();
+        program.set_used();
+        let cutout = CutoutUniforms::for_program(&program);
+        ShadowProgram { program, cutout, light_space_loc }
+    }
+
+    pub fn set_light_space(&self, light_space: &Mat4x4) {
+        self.light_space_loc.set_uniform_matrix4fv(light_space);
+    }
+}
+
+/// Draws distance from a point light into one face of a cube map at a time.
+pub struct PointShadowProgram {
+    pub program: Program,
+    pub cutout: CutoutUniforms,
+    light_space_loc: Uniform,
+    light_pos_loc: Uniform,
+    range_loc: Uniform,
+}
+
+impl PointShadowProgram {
+    pub fn from_shaders(vert: &Shader, frag: &Shader) -> PointShadowProgram {
+        let program = Program::from_shaders(vert, frag).unwrap();
+        let loc = |name: &str| get_uniform_location(program.id, name).unwrap();
+        program.set_used();
+        PointShadowProgram {
+            cutout: CutoutUniforms::for_program(&program),
+            light_space_loc: loc("light_space"),
+            light_pos_loc: loc("light_pos"),
+            range_loc: loc("range"),
+            program,
+        }
+    }
+
+    pub fn set_light(&self, shadow: &PointShadow) {
+        self.light_pos_loc.set_uniform_vec3(&shadow.position);
+        self.range_loc.set_uniform_1f(shadow.range);
+    }
+
+    /// One of the matrices from `cube_face_matrices`.
+    pub fn set_face(&self, light_space: &Mat4x4) {
+        self.light_space_loc.set_uniform_matrix4fv(light_space);
+    }
+}
+
+/// Draws the skybox, the cube map is read from texture unit 0.
+pub struct SkyProgram {
+    pub program: Program,
+    view_loc: Uniform,
+    projection_loc: Uniform,
+}
+
+impl SkyProgram {
+    pub fn from_shaders(vert: &Shader, frag: &Shader) -> SkyProgram {
+        let program = Program::from_shaders(vert, frag).unwrap();
+        let view_loc = get_uniform_location(program.id, "view").unwrap();
+        let projection_loc = get_uniform_location(program.id, "projection").unwrap();
+        SkyProgram { program, view_loc, projection_loc }
+    }
+
+    /// Takes the camera's full view, the translation is ignored.
+    pub fn set_vp(&self, view: &Mat4x4, projection: &Mat4x4) {
+        self.view_loc.set_uniform_matrix4fv(view);
+        self.projection_loc.set_uniform_matrix4fv(projection);
+    }
+}
+
+/// A full-screen pass. Every effect has its own uniforms so they're looked
+/// up by name, from the list given when the program is built.
+pub struct PostProgram {
+    pub program: Program,
+    uniforms: Vec<(String, Uniform)>,
+}
+
+impl PostProgram {
+    pub fn from_shaders(vert: &Shader, frag: &Shader, names: &[&str]) -> PostProgram {
+        let program = Program::from_shaders(vert, frag).unwrap();
+        let uniforms = names
+            .iter()
+            .map(|name| (name.to_string(), get_uniform_location(program.id, name).unwrap()))
+            .collect();
+        PostProgram { program, uniforms }
+    }
+
+    fn uniform(&self, name: &str) -> &Uniform {
+        self.uniforms
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, uniform)| uniform)
+            .unwrap_or_else(|| panic!("`{}` wasn't looked up for this program", name))
+    }
+
+    /// Also how samplers are pointed at texture units.
+    pub fn set_1i(&self, name: &str, value: i32) {
+        self.uniform(name).set_uniform_1i(value);
     }
 
-    pub fn set_light(&self, pos: &Vec3, color: &Vec3) {
-        self.color_loc.set_uniform_vec3(color);
-        self.pos_loc.set_uniform_vec3(pos);
+    pub fn set_1f(&self, name: &str, value: f32) {
+        self.uniform(name).set_uniform_1f(value);
     }
 
-    pub fn set_object_color(&self, color: &Vec3) {
-        self.object_color_loc.set_uniform_vec3(color);
+    pub fn set_2f(&self, name: &str, x: f32, y: f32) {
+        self.uniform(name).set_uniform_2f(x, y);
     }
 }
 
@@ -67,13 +422,18 @@ pub struct ModelProgram {
     pub program: Program,
     pub mvp: MVPUniforms,
     pub lights: LightUniforms,
+    pub material: MaterialUniforms,
+    pub shadow: ShadowUniforms,
 }
 
 impl ModelProgram {
-    pub fn from_shaders(vert: &Shader, frag: &Shader) -> ModelProgram {
+    pub fn from_shaders(vert: &Shader, frag: &Shader, max_lights: usize) -> ModelProgram {
         let program = Program::from_shaders(vert, frag).unwrap();
         let mvp = MVPUniforms::for_program(&program);
-        let lights = LightUniforms::for_program(&program);
-        ModelProgram { program, mvp, lights }
+        let lights = LightUniforms::for_program(&program, max_lights);
+        program.set_used();
+        let material = MaterialUniforms::for_program(&program);
+        let shadow = ShadowUniforms::for_program(&program);
+        ModelProgram { program, mvp, lights, material, shadow }
     }
 }
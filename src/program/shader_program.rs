@@ -12,6 +12,26 @@ pub fn load_shader_file(
     shader_from_source(cstr_src, shader_type).unwrap()
 }
 
+/// Like `load_shader_file` with a `#define` for each of `defines` added
+/// after the `#version` line, for sizes the shader can't know up front.
+pub fn load_shader_file_with_defines(
+    path: &str,
+    shader_type: gl::types::GLuint,
+    defines: &[(&str, usize)]
+) -> Shader {
+    let source = fs::read_to_string(path).unwrap();
+    let mut lines = source.splitn(2, '\n');
+    let version = lines.next().unwrap();
+    let mut defined = format!("{}\n", version);
+    for (name, value) in defines {
+        defined.push_str(&format!("#define {} {}\n", name, value));
+    }
+    defined.push_str(lines.next().unwrap_or(""));
+
+    let cstr_src = &CString::new(defined).unwrap();
+    shader_from_source(cstr_src, shader_type).unwrap()
+}
+
 pub struct Program {
     pub id: gl::types::GLuint,
 }
@@ -52,4 +52,22 @@ impl Uniform {
             );
         }
     }
+
+    pub fn set_uniform_1i(&self, value: i32) {
+        unsafe {
+            gl::Uniform1i(self.id, value);
+        }
+    }
+
+    pub fn set_uniform_1f(&self, value: f32) {
+        unsafe {
+            gl::Uniform1f(self.id, value);
+        }
+    }
+
+    pub fn set_uniform_2f(&self, x: f32, y: f32) {
+        unsafe {
+            gl::Uniform2f(self.id, x, y);
+        }
+    }
 }
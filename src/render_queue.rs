@@ -0,0 +1,161 @@
+use glm::Vec3;
+use std::cmp::Ordering;
+use super::gl_state::GlState;
+use super::material::Material;
+use super::mesh::Mesh;
+use super::program::Program;
+use super::transform::Transform;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Pass {
+    /// Depth only, into a shadow map.
+    Shadow,
+    Opaque,
+    /// Blended over everything drawn before it.
+    Transparent,
+}
+
+pub struct DrawItem<'a> {
+    pub pass: Pass,
+    pub program: &'a Program,
+    pub mesh: &'a Mesh,
+    pub material: &'a Material,
+    pub receives_shadows: bool,
+    pub transform: Transform,
+}
+
+/// What draws are ordered by, the most expensive state to change first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SortKey {
+    pub pass: Pass,
+    pub program: gl::types::GLuint,
+    pub texture: gl::types::GLuint,
+    pub vao: gl::types::GLuint,
+}
+
+impl<'a> DrawItem<'a> {
+    pub fn sort_key(&self) -> SortKey {
+        SortKey {
+            pass: self.pass,
+            program: self.program.id,
+            texture: self.material.diffuse_map.unwrap_or(0),
+            vao: self.mesh.vao(),
+        }
+    }
+
+    /// Whether both can go in one instanced draw, nothing set between draws
+    /// may differ.
+    fn batches_with(&self, other: &DrawItem) -> bool {
+        self.sort_key() == other.sort_key()
+            && std::ptr::eq(self.mesh, other.mesh)
+            && self.material == other.material
+            && self.receives_shadows == other.receives_shadows
+    }
+}
+
+/// Draws collected over a pass, sorted to change as little GL state as
+/// possible before being drawn.
+pub struct RenderQueue<'a> {
+    items: Vec<DrawItem<'a>>,
+}
+
+impl<'a> RenderQueue<'a> {
+    pub fn new() -> RenderQueue<'a> {
+        RenderQueue { items: Vec::new() }
+    }
+
+    pub fn push(&mut self, item: DrawItem<'a>) {
+        self.items.push(item);
+    }
+
+    /// Sorts and draws everything, one instanced draw per run of matching
+    /// items. `set_material` is given each run's material and whether it
+    /// receives shadows, after its program is in use.
+    pub fn draw(
+        mut self,
+        state: &mut GlState,
+        set_material: impl FnMut(&mut GlState, &Material, bool)
+    ) {
+        self.items.sort_by_key(DrawItem::sort_key);
+        self.draw_runs(state, set_material);
+    }
+
+    /// Like `draw`, but furthest from `eye` first so blended surfaces mix
+    /// with what's behind them. Only neighbours get instanced together.
+    pub fn draw_back_to_front(
+        mut self,
+        state: &mut GlState,
+        eye: &Vec3,
+        set_material: impl FnMut(&mut GlState, &Material, bool)
+    ) {
+        sort_back_to_front(&mut self.items, eye, |item| item.transform.position);
+        self.draw_runs(state, set_material);
+    }
+
+    fn draw_runs(
+        self,
+        state: &mut GlState,
+        mut set_material: impl FnMut(&mut GlState, &Material, bool)
+    ) {
+        let mut start = 0;
+        while start < self.items.len() {
+            let first = &self.items[start];
+            let run = self.items[start..].iter().take_while(|item| item.batches_with(first));
+            let transforms: Vec<_> = run.map(|item| item.transform).collect();
+
+            state.use_program(first.program);
+            set_material(state, first.material, first.receives_shadows);
+            first.mesh.draw_instanced(state, &transforms);
+            start += transforms.len();
+        }
+    }
+}
+
+/// Orders by distance from `eye`, furthest first.
+pub fn sort_back_to_front<T>(items: &mut [T], eye: &Vec3, position: impl Fn(&T) -> Vec3) {
+    items.sort_by(|a, b| {
+        let a = glm::distance2(eye, &position(a));
+        let b = glm::distance2(eye, &position(b));
+        b.partial_cmp(&a).unwrap_or(Ordering::Equal)
+    });
+}
+
+mod tests {
+
+    #[test]
+    fn test_sort_key_orders_program_then_texture_then_vao() {
+        let key = |program, texture, vao| super::SortKey {
+            pass: super::Pass::Opaque,
+            program,
+            texture,
+            vao,
+        };
+        let mut keys = vec![key(2, 1, 1), key(1, 2, 1), key(1, 1, 2), key(1, 1, 1)];
+        keys.sort();
+        assert_eq!(keys, vec![key(1, 1, 1), key(1, 1, 2), key(1, 2, 1), key(2, 1, 1)]);
+
+        let shadow = super::SortKey { pass: super::Pass::Shadow, ..key(3, 3, 3) };
+        keys.push(shadow);
+        keys.sort();
+        assert_eq!(keys[0], shadow);
+    }
+
+    #[test]
+    fn test_sort_back_to_front() {
+        let eye = glm::vec3(0.0, 0.0, 0.0);
+        let mut positions = vec![
+            glm::vec3(0.0, 0.0, 2.0),
+            glm::vec3(-5.0, 0.0, 0.0),
+            glm::vec3(0.0, 1.0, 0.0),
+            glm::vec3(3.0, 0.0, -3.0),
+        ];
+        super::sort_back_to_front(&mut positions, &eye, |position| *position);
+
+        assert_eq!(positions, vec![
+            glm::vec3(-5.0, 0.0, 0.0),
+            glm::vec3(3.0, 0.0, -3.0),
+            glm::vec3(0.0, 0.0, 2.0),
+            glm::vec3(0.0, 1.0, 0.0),
+        ]);
+    }
+}
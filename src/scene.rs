@@ -0,0 +1,17 @@
+use glm::Vec3;
+use super::light::Light;
+use super::model::Model;
+
+/// What gets drawn and what lights it.
+pub struct Scene {
+    pub models: Vec<Model>,
+    pub lights: Vec<Light>,
+    /// Light reaching everything regardless of the lights.
+    pub ambient: Vec3,
+}
+
+impl Scene {
+    pub fn new(models: Vec<Model>, lights: Vec<Light>) -> Scene {
+        Scene { models, lights, ambient: glm::vec3(0.1, 0.1, 0.1) }
+    }
+}
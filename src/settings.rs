@@ -0,0 +1,280 @@
+use std::fmt;
+use std::fs;
+use std::io;
+
+// More lights than this risks running out of fragment shader uniforms.
+const MAX_LIGHTS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fullscreen {
+    Off,
+    /// Covers the monitor at its current resolution, quick to switch in and
+    /// out of.
+    Borderless,
+    /// Takes over the monitor at our own resolution.
+    Exclusive,
+}
+
+/// Display settings, anything missing from the file keeps its default.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Settings {
+    pub width: u32,
+    pub height: u32,
+    /// Vertical field of view in degrees.
+    pub fov: f32,
+    pub vsync: bool,
+    pub fullscreen: Fullscreen,
+    /// Samples per pixel for antialiasing, 0 is off.
+    pub msaa: i32,
+    /// Of the screen, colours are encoded with its inverse.
+    pub gamma: f32,
+    /// Multiplies the final image, before gamma.
+    pub brightness: f32,
+    /// Index into the connected monitors, 0 is usually the primary.
+    pub monitor: usize,
+    /// Degrees turned per pixel of mouse motion.
+    pub mouse_sensitivity_x: f32,
+    pub mouse_sensitivity_y: f32,
+    pub invert_y: bool,
+    pub mouse_smoothing: f32,
+    pub mouse_acceleration: f32,
+    /// Skip the OS's pointer acceleration where glfw supports it.
+    pub raw_mouse: bool,
+    /// Most lights shaded per draw, extra lights are ignored. At least 1 and
+    /// no more than 32.
+    pub max_lights: usize,
+    /// Width and height of shadow maps in texels.
+    pub shadow_resolution: i32,
+    /// Depth offset that keeps surfaces from shadowing themselves.
+    pub shadow_bias: f32,
+    /// How far around the camera the sun's shadows reach.
+    pub shadow_distance: f32,
+    /// How many of the nearest point lights cast shadows, up to
+    /// `shadow::MAX_POINT_SHADOWS`.
+    pub point_shadows: usize,
+    /// Width and height of each face of a point light's shadow cube map.
+    pub point_shadow_resolution: i32,
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(io::Error),
+    UnknownSetting(String),
+    BadValue(String, String),
+    Malformed(String),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SettingsError::Io(err) => write!(f, "{}", err),
+            SettingsError::UnknownSetting(name) => write!(f, "unknown setting `{}`", name),
+            SettingsError::BadValue(name, value) => {
+                write!(f, "bad value `{}` for setting `{}`", value, name)
+            }
+            SettingsError::Malformed(line) => write!(f, "expected `setting = value`, got `{}`", line),
+        }
+    }
+}
+
+impl From<io::Error> for SettingsError {
+    fn from(err: io::Error) -> SettingsError {
+        SettingsError::Io(err)
+    }
+}
+
+type Result<T> = std::result::Result<T, SettingsError>;
+
+impl Settings {
+    pub fn new() -> Settings {
+        Settings {
+            width: 1920,
+            height: 1080,
+            fov: 60.0,
+            vsync: true,
+            fullscreen: Fullscreen::Off,
+            msaa: 4,
+            gamma: 2.2,
+            brightness: 1.0,
+            monitor: 0,
+            mouse_sensitivity_x: 0.5,
+            mouse_sensitivity_y: 0.5,
+            invert_y: false,
+            mouse_smoothing: 0.0,
+            mouse_acceleration: 0.0,
+            raw_mouse: true,
+            max_lights: 8,
+            shadow_resolution: 2048,
+            shadow_bias: 0.005,
+            shadow_distance: 40.0,
+            point_shadows: 2,
+            point_shadow_resolution: 512,
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Settings> {
+        let src = fs::read_to_string(path)?;
+        Settings::parse(&src)
+    }
+
+    /// Reads lines of `setting = value` in the same format as the bindings
+    /// file.
+    pub fn parse(src: &str) -> Result<Settings> {
+        let mut settings = Settings::new();
+
+        for line in src.lines() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut sides = line.splitn(2, '=');
+            let name = sides.next().unwrap().trim();
+            let value = sides
+                .next()
+                .ok_or_else(|| SettingsError::Malformed(line.to_string()))?
+                .trim();
+            let bad_value = || SettingsError::BadValue(name.to_string(), value.to_string());
+
+            match name {
+                "width" => {
+                    let width: u32 = value.parse().map_err(|_| bad_value())?;
+                    if width < 1 {
+                        return Err(bad_value());
+                    }
+                    settings.width = width;
+                }
+                "height" => {
+                    let height: u32 = value.parse().map_err(|_| bad_value())?;
+                    if height < 1 {
+                        return Err(bad_value());
+                    }
+                    settings.height = height;
+                }
+                "fov" => settings.fov = value.parse().map_err(|_| bad_value())?,
+                "vsync" => settings.vsync = parse_switch(value).ok_or_else(bad_value)?,
+                "msaa" => {
+                    let msaa: i32 = value.parse().map_err(|_| bad_value())?;
+                    if msaa < 0 {
+                        return Err(bad_value());
+                    }
+                    settings.msaa = msaa;
+                }
+                "gamma" => {
+                    let gamma: f32 = value.parse().map_err(|_| bad_value())?;
+                    if !gamma.is_finite() || gamma <= 0.0 {
+                        return Err(bad_value());
+                    }
+                    settings.gamma = gamma;
+                }
+                "brightness" => {
+                    let brightness: f32 = value.parse().map_err(|_| bad_value())?;
+                    if !brightness.is_finite() || brightness < 0.0 {
+                        return Err(bad_value());
+                    }
+                    settings.brightness = brightness;
+                }
+                "monitor" => settings.monitor = value.parse().map_err(|_| bad_value())?,
+                "mouse_sensitivity_x" => {
+                    settings.mouse_sensitivity_x = value.parse().map_err(|_| bad_value())?
+                }
+                "mouse_sensitivity_y" => {
+                    settings.mouse_sensitivity_y = value.parse().map_err(|_| bad_value())?
+                }
+                "invert_y" => settings.invert_y = parse_switch(value).ok_or_else(bad_value)?,
+                "mouse_smoothing" => {
+                    settings.mouse_smoothing = value.parse().map_err(|_| bad_value())?
+                }
+                "mouse_acceleration" => {
+                    settings.mouse_acceleration = value.parse().map_err(|_| bad_value())?
+                }
+                "raw_mouse" => settings.raw_mouse = parse_switch(value).ok_or_else(bad_value)?,
+                "max_lights" => {
+                    let max_lights: usize = value.parse().map_err(|_| bad_value())?;
+                    if max_lights < 1 {
+                        return Err(bad_value());
+                    }
+                    settings.max_lights = max_lights.min(MAX_LIGHTS);
+                }
+                "shadow_resolution" => {
+                    let resolution: i32 = value.parse().map_err(|_| bad_value())?;
+                    if resolution < 1 {
+                        return Err(bad_value());
+                    }
+                    settings.shadow_resolution = resolution;
+                }
+                "shadow_bias" => settings.shadow_bias = value.parse().map_err(|_| bad_value())?,
+                "shadow_distance" => {
+                    settings.shadow_distance = value.parse().map_err(|_| bad_value())?
+                }
+                "point_shadows" => {
+                    settings.point_shadows = value.parse().map_err(|_| bad_value())?
+                }
+                "point_shadow_resolution" => {
+                    let resolution: i32 = value.parse().map_err(|_| bad_value())?;
+                    if resolution < 1 {
+                        return Err(bad_value());
+                    }
+                    settings.point_shadow_resolution = resolution;
+                }
+                "fullscreen" => {
+                    settings.fullscreen = match value {
+                        "off" => Fullscreen::Off,
+                        "borderless" => Fullscreen::Borderless,
+                        "exclusive" => Fullscreen::Exclusive,
+                        _ => return Err(bad_value()),
+                    }
+                }
+                _ => return Err(SettingsError::UnknownSetting(name.to_string())),
+            }
+        }
+
+        Ok(settings)
+    }
+}
+
+fn parse_switch(value: &str) -> Option<bool> {
+    match value {
+        "on" => Some(true),
+        "off" => Some(false),
+        _ => None,
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_parse_keeps_defaults() {
+        let settings = super::Settings::parse("
+            # comments and blank lines are skipped
+
+            width = 1280
+            height = 720
+            fullscreen = borderless
+            invert_y = on
+        ").unwrap();
+
+        assert_eq!(settings.width, 1280);
+        assert_eq!(settings.height, 720);
+        assert_eq!(settings.fullscreen, super::Fullscreen::Borderless);
+        assert!(settings.invert_y);
+        assert_eq!(settings.fov, super::Settings::new().fov);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(super::Settings::parse("contrast = 11").is_err());
+        assert!(super::Settings::parse("vsync = sometimes").is_err());
+        assert!(super::Settings::parse("width 1280").is_err());
+        assert!(super::Settings::parse("max_lights = 0").is_err());
+        assert!(super::Settings::parse("shadow_resolution = 0").is_err());
+        assert!(super::Settings::parse("point_shadow_resolution = -512").is_err());
+        assert!(super::Settings::parse("gamma = 0").is_err());
+        assert!(super::Settings::parse("gamma = -2.2").is_err());
+        assert!(super::Settings::parse("brightness = -0.5").is_err());
+        assert!(super::Settings::parse("width = 0").is_err());
+        assert!(super::Settings::parse("height = 0").is_err());
+        assert!(super::Settings::parse("msaa = -4").is_err());
+        assert_eq!(super::Settings::parse("max_lights = 1000").unwrap().max_lights, super::MAX_LIGHTS);
+    }
+}
@@ -0,0 +1,329 @@
+use glm::{Mat4x4, Vec3};
+use super::light::{Light, LightKind};
+
+// Spot and point light shadows start this close to the lamp.
+const SHADOW_NEAR: f32 = 0.1;
+
+/// Most point lights with shadows at once, matches the size of
+/// `point_shadow_maps` in frag.shdr.
+pub const MAX_POINT_SHADOWS: usize = 4;
+
+/// A depth texture rendered from a light's point of view. Anything further
+/// from the light than the depth stored for its texel is in shadow.
+pub struct ShadowMap {
+    fbo: gl::types::GLuint,
+    pub depth_texture: gl::types::GLuint,
+    pub resolution: i32,
+}
+
+impl ShadowMap {
+    pub fn new(resolution: i32) -> ShadowMap {
+        let mut fbo: gl::types::GLuint = 0;
+        let mut depth_texture: gl::types::GLuint = 0;
+        unsafe {
+            gl::GenTextures(1, &mut depth_texture);
+            gl::BindTexture(gl::TEXTURE_2D, depth_texture);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::DEPTH_COMPONENT as i32,
+                resolution,
+                resolution,
+                0,
+                gl::DEPTH_COMPONENT,
+                gl::FLOAT,
+                std::ptr::null()
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+            // Outside the map counts as lit rather than smearing its edges
+            // across the rest of the world.
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_BORDER as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_BORDER as i32);
+            let border = [1.0f32, 1.0, 1.0, 1.0];
+            gl::TexParameterfv(gl::TEXTURE_2D, gl::TEXTURE_BORDER_COLOR, border.as_ptr());
+
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                gl::TEXTURE_2D,
+                depth_texture,
+                0
+            );
+            // Depth only, there's no colour to write.
+            gl::DrawBuffer(gl::NONE);
+            gl::ReadBuffer(gl::NONE);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+
+        ShadowMap { fbo, depth_texture, resolution }
+    }
+
+    /// Renders into the map until another framebuffer is bound.
+    /// The viewport is left at the map's size.
+    pub fn bind_for_writing(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::Viewport(0, 0, self.resolution, self.resolution);
+            gl::Clear(gl::DEPTH_BUFFER_BIT);
+        }
+    }
+}
+
+impl Drop for ShadowMap {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.fbo);
+            gl::DeleteTextures(1, &self.depth_texture);
+        }
+    }
+}
+
+/// Six depth textures around a point light, one per face of a cube. They
+/// hold distance from the light divided by its range rather than the usual
+/// projected depth, so the main shader can compare against any direction.
+pub struct CubeShadowMap {
+    fbo: gl::types::GLuint,
+    pub depth_cubemap: gl::types::GLuint,
+    pub resolution: i32,
+}
+
+impl CubeShadowMap {
+    pub fn new(resolution: i32) -> CubeShadowMap {
+        let mut fbo: gl::types::GLuint = 0;
+        let mut depth_cubemap: gl::types::GLuint = 0;
+        unsafe {
+            gl::GenTextures(1, &mut depth_cubemap);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, depth_cubemap);
+            for face in 0..6 {
+                gl::TexImage2D(
+                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + face,
+                    0,
+                    gl::DEPTH_COMPONENT as i32,
+                    resolution,
+                    resolution,
+                    0,
+                    gl::DEPTH_COMPONENT,
+                    gl::FLOAT,
+                    std::ptr::null()
+                );
+            }
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+            for &wrap in &[gl::TEXTURE_WRAP_S, gl::TEXTURE_WRAP_T, gl::TEXTURE_WRAP_R] {
+                gl::TexParameteri(gl::TEXTURE_CUBE_MAP, wrap, gl::CLAMP_TO_EDGE as i32);
+            }
+
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            gl::DrawBuffer(gl::NONE);
+            gl::ReadBuffer(gl::NONE);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+
+        CubeShadowMap { fbo, depth_cubemap, resolution }
+    }
+
+    /// Like `ShadowMap::bind_for_writing`, for the face `face` matrix of
+    /// `cube_face_matrices` looks through.
+    pub fn bind_face_for_writing(&self, face: usize) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                gl::TEXTURE_CUBE_MAP_POSITIVE_X + face as u32,
+                self.depth_cubemap,
+                0
+            );
+            gl::Viewport(0, 0, self.resolution, self.resolution);
+            gl::Clear(gl::DEPTH_BUFFER_BIT);
+        }
+    }
+}
+
+impl Drop for CubeShadowMap {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.fbo);
+            gl::DeleteTextures(1, &self.depth_cubemap);
+        }
+    }
+}
+
+/// A point light picked to have its shadows drawn this frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointShadow {
+    /// Position in the scene's list of lights.
+    pub light: usize,
+    pub position: Vec3,
+    pub range: f32,
+}
+
+/// The `count` shadow casting point lights nearest `pos`, nearest first.
+/// Lights too far away to reach `pos` still count, their shadows can fall
+/// somewhere in view.
+pub fn nearest_point_shadows(lights: &[Light], pos: &Vec3, count: usize) -> Vec<PointShadow> {
+    let mut shadows: Vec<PointShadow> = lights
+        .iter()
+        .enumerate()
+        .filter(|(_, light)| light.casts_shadows)
+        .filter_map(|(i, light)| match light.kind {
+            LightKind::Point { position, range } => Some(PointShadow { light: i, position, range }),
+            _ => None,
+        })
+        .collect();
+    shadows.sort_by(|a, b| {
+        let a = glm::distance2(&a.position, pos);
+        let b = glm::distance2(&b.position, pos);
+        a.partial_cmp(&b).unwrap()
+    });
+    shadows.truncate(count);
+    shadows
+}
+
+/// Projection and view for each face of a cube map around `position`, in
+/// the order GL numbers them: +x, -x, +y, -y, +z, -z.
+pub fn cube_face_matrices(position: &Vec3, range: f32) -> [Mat4x4; 6] {
+    let projection = glm::perspective(1.0, std::f32::consts::FRAC_PI_2, SHADOW_NEAR, range);
+    // Cube map faces are stored upside down compared to everything else.
+    let faces = [
+        (glm::vec3(1.0, 0.0, 0.0), glm::vec3(0.0, -1.0, 0.0)),
+        (glm::vec3(-1.0, 0.0, 0.0), glm::vec3(0.0, -1.0, 0.0)),
+        (glm::vec3(0.0, 1.0, 0.0), glm::vec3(0.0, 0.0, 1.0)),
+        (glm::vec3(0.0, -1.0, 0.0), glm::vec3(0.0, 0.0, -1.0)),
+        (glm::vec3(0.0, 0.0, 1.0), glm::vec3(0.0, -1.0, 0.0)),
+        (glm::vec3(0.0, 0.0, -1.0), glm::vec3(0.0, -1.0, 0.0)),
+    ];
+    let face = |i: usize| {
+        let (direction, up) = faces[i];
+        projection * glm::look_at(position, &(position + direction), &up)
+    };
+    [face(0), face(1), face(2), face(3), face(4), face(5)]
+}
+
+/// Projection and view of `light` as one matrix, taking world space to the
+/// shadow map's clip space. Directional lights cover `radius` around
+/// `focus`; point lights need `cube_face_matrices` instead so get `None`.
+pub fn light_space_matrix(light: &Light, focus: &Vec3, radius: f32) -> Option<Mat4x4> {
+    match light.kind {
+        LightKind::Directional { direction } => {
+            // Far enough back that nothing within the radius is behind the
+            // light.
+            let eye = focus - direction * radius * 2.0;
+            let view = glm::look_at(&eye, focus, &up_for(&direction));
+            let projection = glm::ortho(-radius, radius, -radius, radius, radius, radius * 3.0);
+            Some(projection * view)
+        }
+        LightKind::Spot { position, direction, range, outer_angle, .. } => {
+            let view = glm::look_at(&position, &(position + direction), &up_for(&direction));
+            let fov = (outer_angle * 2.0).to_radians();
+            let projection = glm::perspective(1.0, fov, SHADOW_NEAR, range);
+            Some(projection * view)
+        }
+        LightKind::Point { .. } => None,
+    }
+}
+
+/// Any up vector that isn't parallel to `direction`.
+fn up_for(direction: &Vec3) -> Vec3 {
+    if direction.y.abs() > 0.99 {
+        glm::vec3(0.0, 0.0, 1.0)
+    } else {
+        glm::vec3(0.0, 1.0, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    fn to_clip(matrix: &glm::Mat4x4, point: glm::Vec3) -> glm::Vec3 {
+        let clip = matrix * glm::vec4(point.x, point.y, point.z, 1.0);
+        glm::vec3(clip.x, clip.y, clip.z) / clip.w
+    }
+
+    #[test]
+    fn test_directional_covers_radius() {
+        let sun = super::Light::directional(glm::vec3(-0.3, -1.0, -0.2), glm::vec3(1.0, 1.0, 1.0));
+        let focus = glm::vec3(10.0, 0.0, 10.0);
+        let matrix = super::light_space_matrix(&sun, &focus, 20.0).unwrap();
+
+        let center = to_clip(&matrix, focus);
+        assert!(center.x.abs() < 0.001 && center.y.abs() < 0.001);
+
+        // Higher up is nearer the sun.
+        let above = to_clip(&matrix, focus + glm::vec3(0.0, 5.0, 0.0));
+        assert!(above.z < center.z);
+
+        for &offset in &[glm::vec3(15.0, 0.0, 0.0), glm::vec3(0.0, 15.0, 0.0), glm::vec3(0.0, 0.0, -15.0)] {
+            let clip = to_clip(&matrix, focus + offset);
+            assert!(clip.x.abs() <= 1.0 && clip.y.abs() <= 1.0 && clip.z.abs() <= 1.0);
+        }
+    }
+
+    #[test]
+    fn test_spot_looks_along_direction() {
+        let spot = super::Light::spot(
+            glm::vec3(0.0, 4.0, 0.0),
+            glm::vec3(0.0, -1.0, 0.0),
+            glm::vec3(1.0, 1.0, 1.0),
+            10.0,
+            20.0,
+            30.0
+        );
+        let matrix = super::light_space_matrix(&spot, &glm::vec3(0.0, 0.0, 0.0), 0.0).unwrap();
+
+        let below = to_clip(&matrix, glm::vec3(0.0, 0.0, 0.0));
+        assert!(below.x.abs() < 0.001 && below.y.abs() < 0.001 && below.z.abs() < 1.0);
+
+        // Outside the 30 degree cone.
+        let beside = to_clip(&matrix, glm::vec3(4.0, 0.0, 0.0));
+        assert!(beside.x.abs() > 1.0 || beside.y.abs() > 1.0);
+
+        let point = super::Light::point(glm::vec3(0.0, 0.0, 0.0), glm::vec3(1.0, 1.0, 1.0), 5.0);
+        assert!(super::light_space_matrix(&point, &glm::vec3(0.0, 0.0, 0.0), 10.0).is_none());
+    }
+
+    #[test]
+    fn test_nearest_point_shadows() {
+        let white = glm::vec3(1.0, 1.0, 1.0);
+        let mut lights = vec![
+            super::Light::directional(glm::vec3(0.0, -1.0, 0.0), white),
+            super::Light::point(glm::vec3(20.0, 0.0, 0.0), white, 10.0),
+            super::Light::point(glm::vec3(5.0, 0.0, 0.0), white, 10.0),
+            super::Light::point(glm::vec3(1.0, 0.0, 0.0), white, 10.0),
+            super::Light::point(glm::vec3(10.0, 0.0, 0.0), white, 10.0),
+        ];
+        for light in lights.iter_mut() {
+            light.casts_shadows = true;
+        }
+        lights[3].casts_shadows = false;
+
+        let shadows = super::nearest_point_shadows(&lights, &glm::vec3(0.0, 0.0, 0.0), 2);
+        let picked: Vec<usize> = shadows.iter().map(|shadow| shadow.light).collect();
+        assert_eq!(picked, vec![2, 4]);
+        assert_eq!(shadows[0].range, 10.0);
+    }
+
+    #[test]
+    fn test_cube_faces_look_along_axes() {
+        let position = glm::vec3(2.0, 3.0, 4.0);
+        let faces = super::cube_face_matrices(&position, 10.0);
+        let axes = [
+            glm::vec3(1.0, 0.0, 0.0),
+            glm::vec3(-1.0, 0.0, 0.0),
+            glm::vec3(0.0, 1.0, 0.0),
+            glm::vec3(0.0, -1.0, 0.0),
+            glm::vec3(0.0, 0.0, 1.0),
+            glm::vec3(0.0, 0.0, -1.0),
+        ];
+        for (face, axis) in faces.iter().zip(&axes) {
+            let ahead = to_clip(face, position + axis * 5.0);
+            assert!(ahead.x.abs() < 0.001 && ahead.y.abs() < 0.001 && ahead.z.abs() < 1.0);
+            let behind = to_clip(face, position - axis * 5.0);
+            assert!(behind.z.abs() > 1.0 || behind.x.abs() > 1.0 || behind.y.abs() > 1.0);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use super::buffer;
+use super::gl_state::GlState;
+use super::texture;
+use super::vertex;
+
+/// Where a map's sky comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SkySource {
+    /// Six images in GL's face order: +x, -x, +y, -y, +z, -z.
+    Faces(Vec<String>),
+    /// One panorama wrapped around the whole sky.
+    Equirectangular(String),
+}
+
+impl SkySource {
+    /// Either one path to a panorama or six comma separated face paths.
+    pub fn parse(src: &str) -> Option<SkySource> {
+        let paths: Vec<String> = src.split(',').map(|path| path.trim().to_string()).collect();
+        if paths.iter().any(|path| path.is_empty()) {
+            return None;
+        }
+        match paths.len() {
+            1 => Some(SkySource::Equirectangular(paths[0].clone())),
+            6 => Some(SkySource::Faces(paths)),
+            _ => None,
+        }
+    }
+}
+
+/// A cube around the camera showing a cube map, drawn behind everything.
+pub struct Skybox {
+    vao: gl::types::GLuint,
+    _position_vbo: buffer::ArrayBuffer,
+    pub cubemap: gl::types::GLuint,
+}
+
+impl Skybox {
+    pub fn new(source: &SkySource) -> Skybox {
+        let cubemap = match source {
+            SkySource::Faces(paths) => texture::prepare_cubemap(paths),
+            SkySource::Equirectangular(path) => texture::prepare_equirect_cubemap(path),
+        };
+
+        let vbo = buffer::ArrayBuffer::new();
+        vbo.bind();
+        vbo.static_draw_data(&cube_verts());
+        vbo.unbind();
+
+        let mut vao: gl::types::GLuint = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+
+            vbo.bind();
+            vertex::position_attrib_pointer();
+            vbo.unbind();
+
+            gl::BindVertexArray(0);
+        }
+
+        Skybox { vao, _position_vbo: vbo, cubemap }
+    }
+
+    /// Expects the sky program in use. Only fills in pixels nothing else has
+    /// been drawn over, so draw it after the opaque geometry.
+    pub fn draw(&self, state: &mut GlState) {
+        unsafe {
+            // The shader pins the sky to the far plane, which the cleared
+            // depth buffer is already at.
+            gl::DepthFunc(gl::LEQUAL);
+            gl::DepthMask(gl::FALSE);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.cubemap);
+        }
+        state.bind_vertex_array(self.vao);
+
+        vertex::draw_arrays(36);
+        state.count_draw(1);
+
+        unsafe {
+            gl::DepthMask(gl::TRUE);
+            gl::DepthFunc(gl::LESS);
+        }
+    }
+}
+
+impl Drop for Skybox {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vao);
+            gl::DeleteTextures(1, &self.cubemap);
+        }
+    }
+}
+
+/// Two triangles for each face of a cube around the origin.
+fn cube_verts() -> Vec<f32> {
+    let corners = [
+        [-1.0, -1.0, -1.0], [1.0, -1.0, -1.0], [1.0, 1.0, -1.0], [-1.0, 1.0, -1.0],
+        [-1.0, -1.0, 1.0], [1.0, -1.0, 1.0], [1.0, 1.0, 1.0], [-1.0, 1.0, 1.0],
+    ];
+    let faces = [
+        [0, 3, 2, 1], // -z
+        [4, 5, 6, 7], // +z
+        [0, 4, 7, 3], // -x
+        [1, 2, 6, 5], // +x
+        [0, 1, 5, 4], // -y
+        [3, 7, 6, 2], // +y
+    ];
+    let mut verts = Vec::with_capacity(36 * 3);
+    for face in &faces {
+        for &corner in &[face[0], face[1], face[2], face[2], face[3], face[0]] {
+            verts.extend_from_slice(&corners[corner]);
+        }
+    }
+    verts
+}
+
+mod tests {
+
+    #[test]
+    fn test_parse_sky_source() {
+        assert_eq!(
+            super::SkySource::parse("assets/sky.png"),
+            Some(super::SkySource::Equirectangular("assets/sky.png".to_string()))
+        );
+
+        let faces = "px.png, nx.png, py.png, ny.png, pz.png, nz.png";
+        match super::SkySource::parse(faces) {
+            Some(super::SkySource::Faces(paths)) => {
+                assert_eq!(paths.len(), 6);
+                assert_eq!(paths[3], "ny.png");
+            }
+            other => panic!("expected six faces, got {:?}", other),
+        }
+
+        assert_eq!(super::SkySource::parse("a.png, b.png"), None);
+        assert_eq!(super::SkySource::parse(""), None);
+    }
+}
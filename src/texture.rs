@@ -1,8 +1,11 @@
-use image::EncodableLayout;
+use image::{EncodableLayout, RgbImage};
 
+/// For colour maps, which are stored in sRGB. GL turns them back to linear
+/// when they're sampled so lighting adds up properly. Alpha is kept, images
+/// without any are fully opaque.
 pub fn prepare_textures(path: &str) -> gl::types::GLuint {
     let mut texture_id: gl::types::GLuint = 0;
-    let image = image::open(path).unwrap().rotate180().to_rgb8();
+    let image = image::open(path).unwrap().rotate180().to_rgba8();
     unsafe {
         gl::GenTextures(1, &mut texture_id);
         gl::BindTexture(gl::TEXTURE_2D, texture_id);
@@ -13,11 +16,11 @@ pub fn prepare_textures(path: &str) -> gl::types::GLuint {
         gl::TexImage2D(
             gl::TEXTURE_2D,
             0,
-            gl::RGB as gl::types::GLint,
+            gl::SRGB8_ALPHA8 as gl::types::GLint,
             image.width() as gl::types::GLint,
             image.height() as gl::types::GLint,
             0,
-            gl::RGB,
+            gl::RGBA,
             gl::UNSIGNED_BYTE,
             image.as_bytes().as_ptr() as *const gl::types::GLvoid
         );
@@ -27,3 +30,148 @@ pub fn prepare_textures(path: &str) -> gl::types::GLuint {
     texture_id
 }
 
+
+/// A colour grading lookup table. Unlike `prepare_textures` it's left the
+/// right way up, clamped and without mipmaps since it's read as data.
+pub fn prepare_lut(path: &str) -> gl::types::GLuint {
+    let mut texture_id: gl::types::GLuint = 0;
+    let image = image::open(path).unwrap().to_rgb8();
+    unsafe {
+        gl::GenTextures(1, &mut texture_id);
+        gl::BindTexture(gl::TEXTURE_2D, texture_id);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as gl::types::GLint);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as gl::types::GLint);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as gl::types::GLint);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as gl::types::GLint);
+        gl::TexImage2D(
+            gl::TEXTURE_2D,
+            0,
+            gl::RGB8 as gl::types::GLint,
+            image.width() as gl::types::GLint,
+            image.height() as gl::types::GLint,
+            0,
+            gl::RGB,
+            gl::UNSIGNED_BYTE,
+            image.as_bytes().as_ptr() as *const gl::types::GLvoid
+        );
+        gl::BindTexture(gl::TEXTURE_2D, 0);
+    }
+    texture_id
+}
+
+/// Which way the texel at `u`, `v` (both -1 to 1, v down the image) of cube
+/// map face `face` looks, in GL's face order: +x, -x, +y, -y, +z, -z.
+fn cube_face_direction(face: usize, u: f32, v: f32) -> glm::Vec3 {
+    match face {
+        0 => glm::vec3(1.0, -v, -u),
+        1 => glm::vec3(-1.0, -v, u),
+        2 => glm::vec3(u, 1.0, v),
+        3 => glm::vec3(u, -1.0, -v),
+        4 => glm::vec3(u, -v, 1.0),
+        _ => glm::vec3(-u, -v, -1.0),
+    }
+}
+
+/// Samples a panorama spanning 360 degrees across and 180 up and down into
+/// six square faces of `size` texels. The middle of the panorama ends up
+/// facing -z.
+pub fn equirect_to_cube_faces(panorama: &RgbImage, size: u32) -> Vec<RgbImage> {
+    let (width, height) = panorama.dimensions();
+    (0..6)
+        .map(|face| {
+            RgbImage::from_fn(size, size, |x, y| {
+                let u = 2.0 * (x as f32 + 0.5) / size as f32 - 1.0;
+                let v = 2.0 * (y as f32 + 0.5) / size as f32 - 1.0;
+                let dir = cube_face_direction(face, u, v).normalize();
+
+                let longitude = dir.x.atan2(-dir.z);
+                let latitude = dir.y.asin();
+                let s = longitude / (2.0 * std::f32::consts::PI) + 0.5;
+                let t = 0.5 - latitude / std::f32::consts::PI;
+                let px = ((s * width as f32) as u32).min(width - 1);
+                let py = ((t * height as f32) as u32).min(height - 1);
+                *panorama.get_pixel(px, py)
+            })
+        })
+        .collect()
+}
+
+fn upload_cubemap(faces: &[RgbImage]) -> gl::types::GLuint {
+    let mut texture_id: gl::types::GLuint = 0;
+    unsafe {
+        gl::GenTextures(1, &mut texture_id);
+        gl::BindTexture(gl::TEXTURE_CUBE_MAP, texture_id);
+        for (i, face) in faces.iter().enumerate() {
+            gl::TexImage2D(
+                gl::TEXTURE_CUBE_MAP_POSITIVE_X + i as u32,
+                0,
+                gl::SRGB8 as gl::types::GLint,
+                face.width() as gl::types::GLint,
+                face.height() as gl::types::GLint,
+                0,
+                gl::RGB,
+                gl::UNSIGNED_BYTE,
+                face.as_bytes().as_ptr() as *const gl::types::GLvoid
+            );
+        }
+        // Clamped so the seams between faces don't pick up the far edge.
+        for &wrap in &[gl::TEXTURE_WRAP_S, gl::TEXTURE_WRAP_T, gl::TEXTURE_WRAP_R] {
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, wrap, gl::CLAMP_TO_EDGE as gl::types::GLint);
+        }
+        gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MIN_FILTER, gl::LINEAR as gl::types::GLint);
+        gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MAG_FILTER, gl::LINEAR as gl::types::GLint);
+        gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+    }
+    texture_id
+}
+
+/// Loads six images in GL's face order: +x, -x, +y, -y, +z, -z. Unlike
+/// `prepare_textures` they aren't flipped, cube maps expect the first row
+/// at the top.
+pub fn prepare_cubemap(paths: &[String]) -> gl::types::GLuint {
+    let faces: Vec<RgbImage> = paths
+        .iter()
+        .map(|path| image::open(path).unwrap().to_rgb8())
+        .collect();
+    upload_cubemap(&faces)
+}
+
+/// Loads a panorama and converts it to a cube map with faces half as tall
+/// as the panorama.
+pub fn prepare_equirect_cubemap(path: &str) -> gl::types::GLuint {
+    let panorama = image::open(path).unwrap().to_rgb8();
+    let size = (panorama.height() / 2).max(1);
+    upload_cubemap(&equirect_to_cube_faces(&panorama, size))
+}
+
+mod tests {
+
+    #[test]
+    fn test_equirect_faces_point_the_right_way() {
+        // Red sky over blue ground, with a band down the middle marked green.
+        let panorama = super::RgbImage::from_fn(64, 32, |x, y| {
+            if (28..36).contains(&x) {
+                image::Rgb([0, 255, 0])
+            } else if y < 16 {
+                image::Rgb([255, 0, 0])
+            } else {
+                image::Rgb([0, 0, 255])
+            }
+        });
+        let faces = super::equirect_to_cube_faces(&panorama, 8);
+        assert_eq!(faces.len(), 6);
+
+        let red = image::Rgb([255, 0, 0]);
+        let blue = image::Rgb([0, 0, 255]);
+        assert_eq!(*faces[2].get_pixel(1, 1), red);
+        assert_eq!(*faces[3].get_pixel(1, 1), blue);
+        // The sides are split across the horizon.
+        assert_eq!(*faces[0].get_pixel(1, 1), red);
+        assert_eq!(*faces[0].get_pixel(1, 6), blue);
+        // Straight ahead down -z is the middle of the panorama.
+        let green = image::Rgb([0, 255, 0]);
+        assert_eq!(*faces[5].get_pixel(3, 1), green);
+        assert_eq!(*faces[5].get_pixel(4, 6), green);
+        assert_ne!(*faces[4].get_pixel(4, 1), green);
+    }
+}
@@ -0,0 +1,48 @@
+use glm::{Mat3x3, Mat4x4, Quat, Vec3};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Transform {
+    pub position: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Transform {
+    pub fn at(position: Vec3) -> Transform {
+        Transform {
+            position,
+            rotation: glm::quat_identity(),
+            scale: glm::vec3(1.0, 1.0, 1.0),
+        }
+    }
+
+    pub fn matrix(&self) -> Mat4x4 {
+        glm::translation(&self.position)
+            * glm::quat_to_mat4(&self.rotation)
+            * glm::scaling(&self.scale)
+    }
+
+    /// Inverse transpose of the model matrix, so normals stay perpendicular
+    /// to their faces under non-uniform scale.
+    pub fn normal_matrix(&self) -> Mat3x3 {
+        let model = glm::mat4_to_mat3(&self.matrix());
+        glm::transpose(&glm::inverse(&model))
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_matrix_applies_scale_rotation_then_translation() {
+        let mut transform = super::Transform::at(glm::vec3(10.0, 0.0, 0.0));
+        transform.scale = glm::vec3(2.0, 2.0, 2.0);
+        transform.rotation = glm::quat_angle_axis(
+            std::f32::consts::FRAC_PI_2,
+            &glm::vec3(0.0, 1.0, 0.0)
+        );
+
+        let moved = transform.matrix() * glm::vec4(1.0, 0.0, 0.0, 1.0);
+        assert!((moved.x - 10.0).abs() < 1e-5);
+        assert!((moved.z - -2.0).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,147 @@
+use super::collide::AABB;
+use super::physics::BodyId;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Occupant {
+    Player,
+    Body(BodyId),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TriggerEventKind {
+    Enter,
+    Stay,
+    Exit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TriggerId(usize);
+
+#[derive(Debug)]
+pub struct TriggerEvent<'a> {
+    pub name: &'a str,
+    pub occupant: Occupant,
+    pub kind: TriggerEventKind,
+}
+
+type Callback = Box<dyn FnMut(&TriggerEvent)>;
+
+/// A non-solid region, nothing collides with it but everything inside it
+/// is reported each update.
+struct Trigger {
+    name: String,
+    volume: AABB,
+    inside: Vec<Occupant>,
+    callbacks: Vec<Callback>,
+}
+
+pub struct Triggers {
+    triggers: Vec<Trigger>,
+}
+
+impl Triggers {
+    pub fn new() -> Triggers {
+        Triggers { triggers: Vec::new() }
+    }
+
+    pub fn add(&mut self, name: &str, volume: AABB) -> TriggerId {
+        self.triggers.push(Trigger {
+            name: name.to_string(),
+            volume,
+            inside: Vec::new(),
+            callbacks: Vec::new(),
+        });
+        TriggerId(self.triggers.len() - 1)
+    }
+
+    pub fn on_event<F>(&mut self, id: TriggerId, callback: F)
+    where
+        F: FnMut(&TriggerEvent) + 'static,
+    {
+        self.triggers[id.0].callbacks.push(Box::new(callback));
+    }
+
+    pub fn is_occupied(&self, id: TriggerId) -> bool {
+        !self.triggers[id.0].inside.is_empty()
+    }
+
+    /// Tests every occupant against every volume, firing `Enter` the first
+    /// update an occupant overlaps, `Stay` while it keeps overlapping and
+    /// `Exit` once it no longer does.
+    pub fn update(&mut self, occupants: &[(Occupant, AABB)]) {
+        for trigger in &mut self.triggers {
+            let mut now_inside = Vec::new();
+
+            for (occupant, bounds) in occupants {
+                if !trigger.volume.intersects(bounds) {
+                    continue;
+                }
+                let kind = if trigger.inside.contains(occupant) {
+                    TriggerEventKind::Stay
+                } else {
+                    TriggerEventKind::Enter
+                };
+                now_inside.push(*occupant);
+                fire(trigger, *occupant, kind);
+            }
+
+            let left: Vec<_> = trigger.inside
+                .iter()
+                .filter(|o| !now_inside.contains(o))
+                .cloned()
+                .collect();
+            for occupant in left {
+                fire(trigger, occupant, TriggerEventKind::Exit);
+            }
+
+            trigger.inside = now_inside;
+        }
+    }
+}
+
+fn fire(trigger: &mut Trigger, occupant: Occupant, kind: TriggerEventKind) {
+    let event = TriggerEvent {
+        name: &trigger.name,
+        occupant,
+        kind,
+    };
+    for callback in &mut trigger.callbacks {
+        callback(&event);
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_enter_stay_exit() {
+        let mut triggers = super::Triggers::new();
+        let door = triggers.add("door", super::AABB {
+            left_top_front: glm::vec3(2.0, 2.0, 2.0),
+            right_bottom_back: glm::vec3(0.0, 0.0, 0.0),
+        });
+
+        let seen = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let recorded = seen.clone();
+        triggers.on_event(door, move |event| {
+            assert_eq!(event.name, "door");
+            recorded.borrow_mut().push(event.kind);
+        });
+
+        let at = |x: f32| super::AABB {
+            left_top_front: glm::vec3(x + 0.5, 1.5, 1.5),
+            right_bottom_back: glm::vec3(x - 0.5, 0.5, 0.5),
+        };
+        let player = super::Occupant::Player;
+        triggers.update(&[(player, at(-5.0))]);
+        triggers.update(&[(player, at(1.0))]);
+        triggers.update(&[(player, at(1.5))]);
+        triggers.update(&[(player, at(5.0))]);
+        triggers.update(&[(player, at(5.0))]);
+
+        assert_eq!(*seen.borrow(), vec![
+            super::TriggerEventKind::Enter,
+            super::TriggerEventKind::Stay,
+            super::TriggerEventKind::Exit,
+        ]);
+    }
+}
@@ -1,7 +1,12 @@
+use super::transform::Transform;
+
 const TRI_VERTS: usize = 3;
 
+/// Floats per vertex in a mesh: position, normal, then texture coordinates.
+pub const VERTEX_FLOATS: usize = 2 * TRI_VERTS + 2;
+
 pub fn vertex_attrib_pointers() {
-    let stride = (2 * TRI_VERTS + 2) * std::mem::size_of::<f32>();
+    let stride = VERTEX_FLOATS * std::mem::size_of::<f32>();
 
     let location = 0;
     let offset = 0;
@@ -16,6 +21,43 @@ pub fn vertex_attrib_pointers() {
     vertex_attrib_pointer(location, 2, stride, offset);
 }
 
+// Model matrix then normal matrix, one of each per instance.
+const INSTANCE_FLOATS: usize = 16 + 9;
+const FIRST_INSTANCE_LOCATION: usize = 3;
+
+/// Per-instance attributes from the bound buffer, laid out as
+/// `instance_floats` writes them. Matrices take one location per column.
+pub fn instance_attrib_pointers() {
+    let stride = INSTANCE_FLOATS * std::mem::size_of::<f32>();
+    // (size, offset in floats) of the model matrix's four columns then the
+    // normal matrix's three.
+    let columns = [(4, 0), (4, 4), (4, 8), (4, 12), (3, 16), (3, 19), (3, 22)];
+    for (i, &(size, offset)) in columns.iter().enumerate() {
+        let location = FIRST_INSTANCE_LOCATION + i;
+        vertex_attrib_pointer(location, size, stride, offset * std::mem::size_of::<f32>());
+        unsafe {
+            gl::VertexAttribDivisor(location as gl::types::GLuint, 1);
+        }
+    }
+}
+
+/// Packs the model and normal matrix of each transform for
+/// `instance_attrib_pointers`.
+pub fn instance_floats(transforms: &[Transform]) -> Vec<f32> {
+    let mut floats = Vec::with_capacity(transforms.len() * INSTANCE_FLOATS);
+    for transform in transforms {
+        floats.extend_from_slice(transform.matrix().as_slice());
+        floats.extend_from_slice(transform.normal_matrix().as_slice());
+    }
+    floats
+}
+
+/// For vertices that are nothing but a position.
+pub fn position_attrib_pointer() {
+    let stride = TRI_VERTS * std::mem::size_of::<f32>();
+    vertex_attrib_pointer(0, TRI_VERTS as gl::types::GLint, stride, 0);
+}
+
 fn vertex_attrib_pointer(location: usize, size: gl::types::GLint, stride: usize, offset: usize) {
     unsafe {
         gl::EnableVertexAttribArray(location as gl::types::GLuint);
@@ -35,3 +77,9 @@ pub fn draw_arrays(num_faces: usize) {
         gl::DrawArrays(gl::TRIANGLES, 0, num_faces as i32);
     }
 }
+
+pub fn draw_arrays_instanced(num_faces: usize, instances: usize) {
+    unsafe {
+        gl::DrawArraysInstanced(gl::TRIANGLES, 0, num_faces as i32, instances as i32);
+    }
+}